@@ -1,24 +1,35 @@
-use chess_core::{Board, search};
-use std::{io::Write, ops};
+use chess_core::{Game, Move, SearchLimits, Searcher};
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 fn random_u32() -> u32 {
 	rand::random()
 }
 
+fn clock() -> Duration {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+}
+
 fn main() {
-	let mut board = Board::initial_position();
-	let mut moves = vec![];
+	let mut game = Game::initial_position();
+	let mut searcher = Searcher::new(true, random_u32, clock);
+	let limits = SearchLimits {
+		time: Some(Duration::from_secs(3)),
+		..SearchLimits::default()
+	};
 	let mut input = String::new();
 	loop {
+		let board = *game.board();
 		println!("{board}");
-		if let Some(game_result) = board.game_result() {
+		if let Some(game_result) = game.result() {
 			println!("Game over: {game_result}");
+			println!(
+				"{}",
+				game.to_pgn(&[("Event", "Casual game"), ("Site", "CLI")])
+			);
 			break;
 		}
-		board.all_moves(|m| {
-			moves.push(m);
-			ops::ControlFlow::Continue(())
-		});
+		let moves = board.legal_moves();
 		println!("Count: {}", moves.len());
 		for (i, m) in moves.iter().enumerate() {
 			println!("{:2}: {}", i + 1, m.format(board, &moves));
@@ -29,16 +40,26 @@ fn main() {
 			input.clear();
 			std::io::stdin().read_line(&mut input).unwrap();
 			let input = input.trim();
+			if let Ok(m) = Move::from_san(&board, input) {
+				break m;
+			}
 			let input = input.parse::<usize>();
 			if let Ok(input) = input {
 				if input == 0 {
-					break search(&board, 5, true, random_u32).expect("Expected to find a move");
+					let result = searcher.search(&board, limits);
+					println!(
+						"Searched to depth {} ({} nodes)",
+						result.depth, result.nodes
+					);
+					if let Some(moves) = result.mate_in() {
+						println!("Mate in {moves}");
+					}
+					break result.best_move.expect("Expected to find a move");
 				} else if input <= moves.len() {
 					break moves[input - 1];
 				}
 			}
 		};
-		board.apply_move(m);
-		moves.clear();
+		game.play(m);
 	}
 }
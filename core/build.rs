@@ -128,10 +128,183 @@ fn generate_bitboards() -> String {
 	result
 }
 
+/// Returns the squares attacked by a slider on square `i` moving in the given
+/// (file, rank) directions, stopping at the first occupied square in each direction.
+/// If `mask` is true, the last square in each direction is omitted, giving the
+/// squares whose occupancy matters for a magic lookup.
+fn slider_attacks(i: usize, directions: &[(i32, i32)], occupancy: u64, mask: bool) -> u64 {
+	let mut result = 0_u64;
+	for &(df, dr) in directions {
+		let (mut file, mut rank) = ((i / 8) as i32, (i % 8) as i32);
+		loop {
+			file += df;
+			rank += dr;
+			if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+				break;
+			}
+			if mask && !((0..8).contains(&(file + df)) && (0..8).contains(&(rank + dr))) {
+				break;
+			}
+			let bit = 1 << (file * 8 + rank);
+			result |= bit;
+			if occupancy & bit != 0 {
+				break;
+			}
+		}
+	}
+	result
+}
+
+/// Finds magic numbers for the given slider by trial and error, and generates
+/// the mask, magic, shift and offset of each square along with the attack table.
+fn generate_magics(
+	name: &str,
+	directions: &[(i32, i32)],
+	next_random: &mut impl FnMut() -> u64,
+) -> String {
+	let mut masks = vec![];
+	let mut magics = vec![];
+	let mut shifts = vec![];
+	let mut offsets = vec![];
+	let mut table = vec![];
+	for i in 0..64 {
+		let mask = slider_attacks(i, directions, 0, true);
+		let bits = mask.count_ones();
+		// enumerate all subsets of the mask using the carry-rippler trick
+		let mut occupancies = vec![];
+		let mut subset = 0_u64;
+		loop {
+			occupancies.push((subset, slider_attacks(i, directions, subset, false)));
+			subset = subset.wrapping_sub(mask) & mask;
+			if subset == 0 {
+				break;
+			}
+		}
+		let mut attacks = vec![0; 1 << bits];
+		let magic = loop {
+			let magic = next_random() & next_random() & next_random();
+			if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+				continue;
+			}
+			attacks.fill(0);
+			let found = occupancies.iter().all(|&(occupancy, attack)| {
+				let index = (occupancy.wrapping_mul(magic) >> (64 - bits)) as usize;
+				// an attack set is never empty, so zero marks an unused entry
+				if attacks[index] == 0 || attacks[index] == attack {
+					attacks[index] = attack;
+					true
+				} else {
+					false
+				}
+			});
+			if found {
+				break magic;
+			}
+		};
+		masks.push(mask);
+		magics.push(magic);
+		shifts.push(64 - bits);
+		offsets.push(table.len());
+		table.extend(attacks);
+	}
+
+	let mut result = String::new();
+	for (suffix, ty, values) in [
+		(
+			"MASKS",
+			"u64",
+			masks
+				.iter()
+				.map(|v| format!("0x{v:016x}"))
+				.collect::<Vec<_>>(),
+		),
+		(
+			"MAGICS",
+			"u64",
+			magics.iter().map(|v| format!("0x{v:016x}")).collect(),
+		),
+		("SHIFTS", "u32", shifts.iter().map(u32::to_string).collect()),
+		(
+			"OFFSETS",
+			"usize",
+			offsets.iter().map(usize::to_string).collect(),
+		),
+	] {
+		result.push_str(&format!(
+			"pub const {name}_MAGIC_{suffix}: [{ty}; 64] = [\n"
+		));
+		for value in values {
+			result.push_str(&format!("\t{value},\n"));
+		}
+		result.push_str("];\n");
+	}
+	result.push_str(&format!(
+		"pub static {name}_ATTACKS: [u64; {}] = [\n",
+		table.len()
+	));
+	for chunk in table.chunks(8) {
+		result.push('\t');
+		for value in chunk {
+			result.push_str(&format!("{value:#x},"));
+		}
+		result.push('\n');
+	}
+	result.push_str("];\n");
+	result
+}
+
+/// Generates the random keys used for Zobrist hashing: 768 piece-square keys, 4
+/// castling keys, 8 en passant file keys and 1 side-to-move key. This is the layout
+/// of Polyglot's `Random64` array, but the values are different, so the keys can't
+/// be used to look up positions in Polyglot `.bin` books.
+fn generate_zobrist_keys() -> String {
+	// splitmix64, so that the keys are stable across builds
+	let mut state = 0x6a09_e667_f3bc_c908_u64;
+	let mut next = || {
+		state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	};
+	let mut result = "pub const ZOBRIST_KEYS: [u64; 781] = [\n".to_string();
+	for _ in 0..781 {
+		result.push_str(&format!("\t0x{:016x},\n", next()));
+	}
+	result.push_str("];\n");
+	result
+}
+
 fn main() {
 	println!("cargo:rerun-if-changed=build.rs");
 
+	// xorshift64, seeded so that the magic numbers are the same in every build
+	let mut state = 0x3243_f6a8_885a_308d_u64;
+	let mut next_random = || {
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		state
+	};
+	let rook_magics = generate_magics(
+		"ROOK",
+		&[(0, 1), (0, -1), (1, 0), (-1, 0)],
+		&mut next_random,
+	);
+	let bishop_magics = generate_magics(
+		"BISHOP",
+		&[(1, 1), (1, -1), (-1, 1), (-1, -1)],
+		&mut next_random,
+	);
+
 	let out_dir = env::var_os("OUT_DIR").unwrap();
 	let dest_path = path::Path::new(&out_dir).join("consts.rs");
-	fs::write(dest_path, generate_bitboards()).unwrap();
+	fs::write(
+		dest_path,
+		generate_bitboards()
+			+ generate_zobrist_keys().as_str()
+			+ rook_magics.as_str()
+			+ bishop_magics.as_str(),
+	)
+	.unwrap();
 }
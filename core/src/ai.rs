@@ -1,177 +1,674 @@
-use std::ops;
+mod eval;
+mod ordering;
+mod tt;
 
-use crate::{Board, Move, Player};
+use crate::{Board, Move, MoveList};
+use core::cmp::Reverse;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use eval::evaluate;
+use ordering::{MoveOrdering, MovePicker, is_quiet, mvv_lva};
+use std::sync::Arc;
+use tt::{Bound, Entry, TranspositionTable};
 
-fn evaluate(board: &Board) -> i32 {
-	let white_value = board.repr.white_pawns.count()
-		+ board.repr.white_knights.count() * 3
-		+ board.repr.white_bishops.count() * 3
-		+ board.repr.white_rooks.count() * 5
-		+ board.repr.white_queens.count() * 9;
+/// Size of the transposition table used by [`Searcher::new`], in megabytes
+const DEFAULT_HASH_SIZE_MB: usize = 16;
 
-	let black_value = board.repr.black_pawns.count()
-		+ board.repr.black_knights.count() * 3
-		+ board.repr.black_bishops.count() * 3
-		+ board.repr.black_rooks.count() * 5
-		+ board.repr.black_queens.count() * 9;
+/// Score for giving checkmate on the current move. Mates further away score one
+/// less for each ply, so that the fastest mate is preferred.
+const MATE: i32 = 30_000;
+/// Scores at least this far from zero are mates
+const MATE_THRESHOLD: i32 = MATE - 1000;
+/// Larger than any score, used as the bounds of the root search window
+const INFINITY: i32 = MATE + 1;
 
-	let diff = white_value as i32 - black_value as i32;
+/// Evaluates a position without searching, recognising checkmate
+fn static_eval(board: &Board, ply: usize) -> i32 {
+	if board.in_check() && board.legal_moves().is_empty() {
+		-(MATE - ply as i32)
+	} else {
+		evaluate(board)
+	}
+}
 
-	match board.current_player {
-		Player::White => diff,
-		Player::Black => -diff,
+/// Scores a position without legal moves: checkmate is a loss for the side to
+/// move, scored by its distance from the root, and stalemate is a draw
+fn no_moves_score(board: &Board, ply: usize) -> i32 {
+	if board.in_check() {
+		-(MATE - ply as i32)
+	} else {
+		0
 	}
 }
 
-fn quiesce(board: &Board, mut alpha: i32, beta: i32) -> i32 {
-	let eval = evaluate(board);
-	if eval >= beta {
-		return beta;
+/// Limits on how long [`Searcher::search`] may run for. The search stops as soon as
+/// any limit is reached, and without limits it only stops when told to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+	/// Maximum depth in plies
+	pub depth: Option<usize>,
+	/// Maximum number of nodes to visit
+	pub nodes: Option<u64>,
+	/// Maximum time to search for, as measured by the searcher's clock
+	pub time: Option<Duration>,
+}
+
+/// The outcome of [`Searcher::search`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+	/// Best move found by the last completed iteration, or a random legal move if
+	/// no iteration completed. `None` if there are no legal moves.
+	pub best_move: Option<Move>,
+	/// Score of the best move for the side to move
+	pub score: i32,
+	/// Depth of the last completed iteration
+	pub depth: usize,
+	/// Number of nodes visited, including any unfinished iteration
+	pub nodes: u64,
+}
+
+impl SearchResult {
+	/// Returns the number of moves until checkmate if the search found a forced
+	/// mate: positive if the side to move mates, negative if it gets mated
+	pub fn mate_in(&self) -> Option<i32> {
+		if self.score >= MATE_THRESHOLD {
+			Some((MATE - self.score + 1) / 2)
+		} else if self.score <= -MATE_THRESHOLD {
+			Some(-(MATE + self.score + 1) / 2)
+		} else {
+			None
+		}
 	}
-	if alpha < eval {
-		alpha = eval;
+}
+
+/// An iterative-deepening searcher, which searches one ply deeper at a time until
+/// it reaches one of its [`SearchLimits`] or is stopped. Its transposition table is
+/// kept between searches, so a game should use the same searcher for every move.
+pub struct Searcher {
+	enable_quiescence: bool,
+	tt: TranspositionTable,
+	ordering: MoveOrdering,
+	/// Only disabled to measure the effect of move ordering
+	move_ordering: bool,
+	random_u32: fn() -> u32,
+	clock: fn() -> Duration,
+	stop: Arc<AtomicBool>,
+	limits: SearchLimits,
+	start_time: Duration,
+	nodes: u64,
+	aborted: bool,
+}
+
+impl Searcher {
+	/// Constructs a new searcher. The clock may return the time since any fixed
+	/// point, and is only used to enforce time limits.
+	pub fn new(enable_quiescence: bool, random_u32: fn() -> u32, clock: fn() -> Duration) -> Self {
+		Self {
+			enable_quiescence,
+			tt: TranspositionTable::new(DEFAULT_HASH_SIZE_MB),
+			ordering: MoveOrdering::new(),
+			move_ordering: true,
+			random_u32,
+			clock,
+			stop: Arc::new(AtomicBool::new(false)),
+			limits: SearchLimits::default(),
+			start_time: Duration::ZERO,
+			nodes: 0,
+			aborted: false,
+		}
+	}
+
+	/// Replaces the transposition table with an empty one of the given size in
+	/// megabytes. A size of 0 disables the table.
+	pub fn set_hash_size(&mut self, size_mb: usize) {
+		self.tt = TranspositionTable::new(size_mb);
+	}
+
+	/// Enables or disables the quiescence search at the leaves of later searches
+	pub fn set_quiescence(&mut self, enable_quiescence: bool) {
+		self.enable_quiescence = enable_quiescence;
 	}
 
-	board.all_moves(|m| {
-		if !m.is_capture(board) {
-			return ops::ControlFlow::Continue(());
+	/// Empties the transposition table and forgets the move ordering statistics,
+	/// e.g. before starting a new game
+	pub fn clear_hash(&mut self) {
+		self.tt.clear();
+		self.ordering = MoveOrdering::new();
+	}
+
+	/// Returns a flag that stops the current search when set, e.g. from another
+	/// thread. It is reset at the start of each search.
+	pub fn stop_handle(&self) -> Arc<AtomicBool> {
+		Arc::clone(&self.stop)
+	}
+
+	/// Searches the given position until one of the limits is reached
+	pub fn search(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+		self.limits = limits;
+		self.start_time = (self.clock)();
+		self.nodes = 0;
+		self.aborted = false;
+		self.stop.store(false, Ordering::Relaxed);
+		self.tt.new_search();
+		self.ordering.new_search();
+
+		let mut moves = board.legal_moves();
+		moves.sort_by_cached_key(|_| (self.random_u32)());
+		// the sort is stable, so equally good moves stay in random order
+		if self.move_ordering {
+			moves.sort_by_key(|&m| Reverse(self.ordering.score(board, m, None, 0)));
 		}
-		let mut new_board = *board;
-		new_board.apply_move(m);
-		let score = -quiesce(&new_board, -beta, -alpha);
-		if score >= beta {
-			alpha = beta;
-			return ops::ControlFlow::Break(());
+		let mut result = SearchResult {
+			best_move: moves.first().copied(),
+			score: 0,
+			depth: 0,
+			nodes: 0,
+		};
+		if moves.len() <= 1 {
+			return result;
 		}
-		if score > alpha {
-			alpha = score;
+		let mut board = *board;
+		for depth in 1..=limits.depth.unwrap_or(usize::MAX) {
+			// the next iteration is unlikely to finish in the remaining time
+			if depth > 1 && self.elapsed_fraction() > 0.5 {
+				break;
+			}
+			let Some((best_index, score)) = self.search_root(&mut board, &moves, depth) else {
+				break;
+			};
+			// search the best move first in the next iteration
+			moves[..=best_index].rotate_right(1);
+			result.best_move = Some(moves[0]);
+			result.score = score;
+			result.depth = depth;
+			// a mate within the search depth can't be improved on by searching deeper
+			if score.abs() >= MATE - depth as i32 {
+				break;
+			}
 		}
-		ops::ControlFlow::Continue(())
-	});
+		result.nodes = self.nodes;
+		result
+	}
 
-	alpha
-}
+	/// Searches each root move to the given depth, returning the index of the best
+	/// move and its score, or `None` if the search was stopped
+	fn search_root(
+		&mut self,
+		board: &mut Board,
+		moves: &[Move],
+		depth: usize,
+	) -> Option<(usize, i32)> {
+		let beta = INFINITY;
+		let mut alpha = -INFINITY;
+		let mut best = None;
+		for (i, &m) in moves.iter().enumerate() {
+			let undo = board.make_move(m);
+			let score = -self.pv_search(board, -beta, -alpha, depth - 1, 1);
+			board.unmake_move(m, undo);
+			if self.aborted {
+				return None;
+			}
+			if best.is_none() || score > alpha {
+				alpha = score;
+				best = Some((i, score));
+			}
+		}
+		best
+	}
 
-fn zw_search(board: &Board, beta: i32, depth: usize, enable_quiescence: bool) -> i32 {
-	if depth == 0 {
-		return if enable_quiescence {
-			quiesce(board, beta - 1, beta)
-		} else {
-			evaluate(board)
-		};
+	/// Returns the fraction of the time limit used so far, or 0 without a time limit
+	fn elapsed_fraction(&self) -> f64 {
+		match self.limits.time {
+			Some(limit) if !limit.is_zero() => {
+				(self.clock)().saturating_sub(self.start_time).as_secs_f64() / limit.as_secs_f64()
+			}
+			Some(_) => 1.0,
+			None => 0.0,
+		}
 	}
 
-	let mut result = beta - 1;
-	board.all_moves(|m| {
-		let mut new_board = *board;
-		new_board.apply_move(m);
-		let score = -zw_search(&new_board, 1 - beta, depth - 1, enable_quiescence);
-		if score >= beta {
-			result = beta;
-			return ops::ControlFlow::Break(());
+	/// Counts a node and returns true if the search should be aborted
+	fn should_stop(&mut self) -> bool {
+		if self.aborted {
+			return true;
 		}
-		ops::ControlFlow::Continue(())
-	});
-	result
-}
+		self.nodes += 1;
+		if self.limits.nodes.is_some_and(|limit| self.nodes > limit) {
+			self.aborted = true;
+		}
+		// checking the clock is comparatively slow, so only do so occasionally
+		if self.nodes.is_multiple_of(1024)
+			&& (self.stop.load(Ordering::Relaxed) || self.elapsed_fraction() >= 1.0)
+		{
+			self.aborted = true;
+		}
+		self.aborted
+	}
 
-fn pv_search(
-	board: &Board,
-	mut alpha: i32,
-	beta: i32,
-	depth: usize,
-	enable_quiescence: bool,
-) -> i32 {
-	if depth == 0 {
-		return quiesce(board, alpha, beta);
+	/// Stores a result in the transposition table, with mate scores counted from
+	/// this node rather than the root so they remain valid in other positions
+	fn store(
+		&mut self,
+		key: u64,
+		depth: usize,
+		ply: usize,
+		bound: Bound,
+		score: i32,
+		best_move: Option<Move>,
+	) {
+		let ply = ply as i32;
+		let score = if score >= MATE_THRESHOLD {
+			score + ply
+		} else if score <= -MATE_THRESHOLD {
+			score - ply
+		} else {
+			score
+		};
+		self.tt.store(key, depth, bound, score, best_move);
 	}
 
-	let mut search_pv = true;
-	board.all_moves(|m| {
-		let mut new_board = *board;
-		new_board.apply_move(m);
+	/// Returns the given moves in the order they should be searched
+	fn pick_moves(
+		&self,
+		board: &Board,
+		moves: MoveList,
+		tt_move: Option<Move>,
+		ply: usize,
+	) -> MovePicker {
+		MovePicker::new(moves, |m| {
+			if self.move_ordering {
+				self.ordering.score(board, m, tt_move, ply)
+			} else {
+				0
+			}
+		})
+	}
 
-		let score = if search_pv {
-			-pv_search(&new_board, -beta, -alpha, depth - 1, enable_quiescence)
+	fn quiesce(&mut self, board: &mut Board, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+		if self.should_stop() {
+			return 0;
+		}
+		let eval = static_eval(board, ply);
+		if eval >= beta {
+			return beta;
+		}
+		if alpha < eval {
+			alpha = eval;
+		}
+
+		let captures = board.capture_moves();
+		let captures = if self.move_ordering {
+			MovePicker::new(captures, mvv_lva)
 		} else {
-			let s = -zw_search(&new_board, -alpha, depth - 1, enable_quiescence);
-			if s > alpha {
-				-pv_search(&new_board, -beta, -alpha, depth - 1, enable_quiescence)
+			MovePicker::new(captures, |_| 0)
+		};
+		for m in captures {
+			// skip captures that lose material
+			if !board.see_ge(m, 0) {
+				continue;
+			}
+			let undo = board.make_move(m);
+			let score = -self.quiesce(board, -beta, -alpha, ply + 1);
+			board.unmake_move(m, undo);
+			if score >= beta {
+				return beta;
+			}
+			if score > alpha {
+				alpha = score;
+			}
+		}
+
+		alpha
+	}
+
+	fn zw_search(&mut self, board: &mut Board, beta: i32, depth: usize, ply: usize) -> i32 {
+		if depth == 0 {
+			return if self.enable_quiescence {
+				self.quiesce(board, beta - 1, beta, ply)
 			} else {
-				s
+				static_eval(board, ply)
+			};
+		}
+		if self.should_stop() {
+			return 0;
+		}
+		let key = board.zobrist_key();
+		let entry = self.tt.probe(key);
+		if let Some(score) = entry.and_then(|entry| tt_cutoff(entry, depth, ply, beta - 1, beta)) {
+			return score;
+		}
+
+		let moves = board.legal_moves();
+		if moves.is_empty() {
+			return no_moves_score(board, ply);
+		}
+		let tt_move = entry.and_then(|entry| entry.best_move);
+		for m in self.pick_moves(board, moves, tt_move, ply) {
+			let undo = board.make_move(m);
+			let score = -self.zw_search(board, 1 - beta, depth - 1, ply + 1);
+			board.unmake_move(m, undo);
+			if self.aborted {
+				return 0;
 			}
-		};
+			if score >= beta {
+				if is_quiet(m) {
+					self.ordering.record_cutoff(m, ply, depth);
+				}
+				self.store(key, depth, ply, Bound::Lower, beta, Some(m));
+				return beta;
+			}
+		}
+		self.store(key, depth, ply, Bound::Upper, beta - 1, None);
+		beta - 1
+	}
 
-		if score >= beta {
-			alpha = beta;
-			return ops::ControlFlow::Break(());
+	fn pv_search(
+		&mut self,
+		board: &mut Board,
+		mut alpha: i32,
+		beta: i32,
+		depth: usize,
+		ply: usize,
+	) -> i32 {
+		if depth == 0 {
+			return self.quiesce(board, alpha, beta, ply);
+		}
+		if self.should_stop() {
+			return 0;
 		}
-		if score > alpha {
-			alpha = score;
-			search_pv = false;
+		let key = board.zobrist_key();
+		let entry = self.tt.probe(key);
+		if let Some(score) = entry.and_then(|entry| tt_cutoff(entry, depth, ply, alpha, beta)) {
+			return score;
+		}
+
+		let moves = board.legal_moves();
+		if moves.is_empty() {
+			return no_moves_score(board, ply);
 		}
-		ops::ControlFlow::Continue(())
-	});
-	alpha
+		let tt_move = entry.and_then(|entry| entry.best_move);
+		let mut best_move = None;
+		let mut search_pv = true;
+		for m in self.pick_moves(board, moves, tt_move, ply) {
+			let undo = board.make_move(m);
+
+			let score = if search_pv {
+				-self.pv_search(board, -beta, -alpha, depth - 1, ply + 1)
+			} else {
+				let s = -self.zw_search(board, -alpha, depth - 1, ply + 1);
+				if s > alpha {
+					-self.pv_search(board, -beta, -alpha, depth - 1, ply + 1)
+				} else {
+					s
+				}
+			};
+			board.unmake_move(m, undo);
+			if self.aborted {
+				return 0;
+			}
+
+			if score >= beta {
+				if is_quiet(m) {
+					self.ordering.record_cutoff(m, ply, depth);
+				}
+				self.store(key, depth, ply, Bound::Lower, beta, Some(m));
+				return beta;
+			}
+			if score > alpha {
+				alpha = score;
+				best_move = Some(m);
+				search_pv = false;
+			}
+		}
+		let bound = if best_move.is_some() {
+			Bound::Exact
+		} else {
+			Bound::Upper
+		};
+		self.store(key, depth, ply, bound, alpha, best_move);
+		alpha
+	}
 }
 
+/// Returns the score to use instead of searching, if the stored entry is deep
+/// enough and its bound allows a cutoff in the given window
+fn tt_cutoff(entry: Entry, depth: usize, ply: usize, alpha: i32, beta: i32) -> Option<i32> {
+	if (entry.depth as usize) < depth {
+		return None;
+	}
+	let ply = ply as i32;
+	let score = if entry.score >= MATE_THRESHOLD {
+		entry.score - ply
+	} else if entry.score <= -MATE_THRESHOLD {
+		entry.score + ply
+	} else {
+		entry.score
+	};
+	match entry.bound {
+		Bound::Exact => Some(score.clamp(alpha, beta)),
+		Bound::Lower if score >= beta => Some(beta),
+		Bound::Upper if score <= alpha => Some(alpha),
+		_ => None,
+	}
+}
+
+/// Searches the given position to a fixed depth. Use a [`Searcher`] to limit the
+/// search by time or nodes instead.
 pub fn search(
 	board: &Board,
 	depth: usize,
 	enable_quiescence: bool,
 	random_u32: fn() -> u32,
 ) -> Option<Move> {
-	let mut moves = vec![];
-	board.all_moves(|m| {
-		moves.push(m);
-		ops::ControlFlow::Continue(())
-	});
-	if moves.is_empty() {
-		return None;
-	}
-	if moves.len() == 1 {
-		return Some(moves[0]);
-	}
-	moves.sort_by_cached_key(|_| random_u32());
-	if depth == 0 {
-		return Some(moves[0]);
-	}
-	let mut alpha = {
-		let mut new_board = *board;
-		new_board.apply_move(moves[0]);
-		-pv_search(&new_board, -10000, 10000, depth - 1, enable_quiescence)
+	let limits = SearchLimits {
+		depth: Some(depth),
+		..SearchLimits::default()
 	};
-	let beta = 10000;
-	let mut best_move = moves[0];
-	for m in moves.into_iter().skip(1) {
-		let mut new_board = *board;
-		new_board.apply_move(m);
-		let score = -pv_search(&new_board, -beta, -alpha, depth - 1, enable_quiescence);
-		if score > alpha {
-			alpha = score;
-			best_move = m;
-		}
-	}
-	Some(best_move)
+	Searcher::new(enable_quiescence, random_u32, || Duration::ZERO)
+		.search(board, limits)
+		.best_move
 }
 
 #[cfg(test)]
 mod tests {
-	use crate::{Board, search};
-	use std::ops;
+	use super::*;
+	use crate::search;
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	fn clock() -> Duration {
+		SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+	}
+
+	fn searcher() -> Searcher {
+		Searcher::new(true, || 0, clock)
+	}
 
 	#[test]
 	fn only_one_move() {
 		let mut board =
 			Board::from_fen("rnbq1bnr/1pppk1pp/p2Pp3/4P1pQ/2B1N3/8/PPP2PPP/R3K1NR b KQ -");
-		let mut moves = vec![];
-		board.all_moves(|m| {
-			moves.push(m);
-			ops::ControlFlow::Continue(())
-		});
+		let moves = board.legal_moves();
 		eprintln!("{moves:?}");
 		let m = search(&board, 3, true, || 0).unwrap();
-		assert_eq!(m.format(board, moves.as_slice()).to_string(), "cxd6");
+		assert_eq!(m.format(board, &moves).to_string(), "cxd6");
 		board.apply_move(m);
 	}
+
+	#[test]
+	fn depth_limit() {
+		let board = Board::initial_position();
+		let limits = SearchLimits {
+			depth: Some(3),
+			..SearchLimits::default()
+		};
+		let result = searcher().search(&board, limits);
+		assert_eq!(result.depth, 3);
+		assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+
+		let limits = SearchLimits {
+			depth: Some(0),
+			..SearchLimits::default()
+		};
+		let result = searcher().search(&board, limits);
+		assert_eq!(result.depth, 0);
+		assert!(result.best_move.is_some());
+
+		let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+		assert_eq!(searcher().search(&stalemate, limits).best_move, None);
+	}
+
+	#[test]
+	fn node_limit() {
+		let board = Board::initial_position();
+		let limits = SearchLimits {
+			nodes: Some(5000),
+			..SearchLimits::default()
+		};
+		let result = searcher().search(&board, limits);
+		assert!(result.nodes <= 5001, "{result:?}");
+		assert!(result.depth >= 1, "{result:?}");
+		assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+
+		// even without a completed iteration there is a move to play
+		let limits = SearchLimits {
+			nodes: Some(1),
+			..SearchLimits::default()
+		};
+		let result = searcher().search(&board, limits);
+		assert_eq!(result.depth, 0);
+		assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+	}
+
+	#[test]
+	fn time_limit() {
+		let board =
+			Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -");
+		let limits = SearchLimits {
+			time: Some(Duration::from_millis(200)),
+			..SearchLimits::default()
+		};
+		let start = clock();
+		let result = searcher().search(&board, limits);
+		assert!(clock() - start < Duration::from_secs(2), "{result:?}");
+		assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+	}
+
+	#[test]
+	fn stop() {
+		let board = Board::initial_position();
+		let mut searcher = searcher();
+		let stop = searcher.stop_handle();
+		let thread = std::thread::spawn(move || {
+			std::thread::sleep(Duration::from_millis(100));
+			stop.store(true, Ordering::Relaxed);
+		});
+		let result = searcher.search(&board, SearchLimits::default());
+		thread.join().unwrap();
+		assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+	}
+
+	#[test]
+	fn transposition_table() {
+		let board =
+			Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+		let limits = SearchLimits {
+			depth: Some(5),
+			..SearchLimits::default()
+		};
+		let mut without_tt = searcher();
+		without_tt.set_hash_size(0);
+		let without_tt = without_tt.search(&board, limits);
+		let mut searcher = searcher();
+		let first = searcher.search(&board, limits);
+		// the table is reused by the next search
+		let second = searcher.search(&board, limits);
+		assert!(first.nodes < without_tt.nodes, "{first:?} {without_tt:?}");
+		assert!(second.nodes < first.nodes, "{second:?} {first:?}");
+		assert!(board.legal_moves().contains(&second.best_move.unwrap()));
+
+		searcher.clear_hash();
+		assert_eq!(searcher.search(&board, limits).nodes, first.nodes);
+	}
+
+	#[test]
+	fn move_ordering() {
+		let limits = SearchLimits {
+			depth: Some(4),
+			..SearchLimits::default()
+		};
+		let mut total = [0, 0];
+		for fen in [
+			"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+			"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+			"r1bq1rk1/ppp2ppp/2np1n2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 0 7",
+			"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+			"8/2k5/3p4/p2P1p2/P4P2/4K3/8/8 w - - 0 1",
+		] {
+			let board = Board::from_fen(fen);
+			let mut nodes = [0, 0];
+			for (ordered, nodes) in [false, true].into_iter().zip(&mut nodes) {
+				let mut searcher = searcher();
+				searcher.move_ordering = ordered;
+				*nodes = searcher.search(&board, limits).nodes;
+			}
+			total[0] += nodes[0];
+			total[1] += nodes[1];
+		}
+		assert!(total[1] * 2 < total[0], "{total:?}");
+	}
+
+	fn search_depth(fen: &str, depth: usize) -> (Board, SearchResult) {
+		let board = Board::from_fen(fen);
+		let limits = SearchLimits {
+			depth: Some(depth),
+			..SearchLimits::default()
+		};
+		let result = searcher().search(&board, limits);
+		(board, result)
+	}
+
+	#[test]
+	fn mate_in_2() {
+		// 1. Ra7 (or Rb7) and mate on the back rank
+		let (_, result) = search_depth("7k/8/8/8/8/8/8/RR5K w - - 0 1", 6);
+		assert_eq!(result.mate_in(), Some(2), "{result:?}");
+		assert!(result.depth < 6);
+		let m = result.best_move.unwrap().to_uci();
+		assert!(["a1a7", "b1b7"].contains(&m.as_str()), "{m}");
+
+		// 1. Nf6+ gxf6 2. Bxf7#
+		let (_, result) = search_depth(
+			"r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1",
+			4,
+		);
+		assert_eq!(result.mate_in(), Some(2), "{result:?}");
+		assert_eq!(result.best_move.unwrap().to_uci(), "d5f6");
+
+		// every move loses to Rb8#
+		let (_, result) = search_depth("7k/R7/8/8/8/p7/8/1R5K b - - 0 1", 4);
+		assert_eq!(result.mate_in(), Some(-1), "{result:?}");
+	}
+
+	#[test]
+	fn mate_in_3() {
+		// 1. Ra6 cuts off the king, then the rooks take turns checking
+		let (mut board, result) = search_depth("8/7k/8/8/8/8/8/RR5K w - - 0 1", 6);
+		assert_eq!(result.mate_in(), Some(3), "{result:?}");
+		board.apply_move(result.best_move.unwrap());
+		let (_, result) = search_depth(&board.to_fen(), 6);
+		assert_eq!(result.mate_in(), Some(-2), "{result:?}");
+
+		let (_, result) = search_depth("8/7k/8/8/8/8/8/RR5K w - - 0 1", 4);
+		assert_eq!(result.mate_in(), None, "{result:?}");
+	}
+
+	#[test]
+	fn stalemate() {
+		// Qf7 wins nothing and stalemates, so every other move is better
+		let (board, result) = search_depth("7k/8/6Q1/8/8/8/8/K7 w - - 0 1", 3);
+		let mut after = board;
+		after.apply_move(result.best_move.unwrap());
+		assert!(!after.legal_moves().is_empty(), "{result:?}");
+		assert!(result.score > 0, "{result:?}");
+	}
 }
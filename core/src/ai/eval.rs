@@ -0,0 +1,402 @@
+use crate::{Bitboard, Board, Piece, Player, Pos};
+use core::ops;
+
+/// A score split into middlegame and endgame parts, which are blended
+/// according to how much material is left on the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Score(i32, i32);
+
+impl ops::Add for Score {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self {
+		Self(self.0 + rhs.0, self.1 + rhs.1)
+	}
+}
+
+impl ops::AddAssign for Score {
+	fn add_assign(&mut self, rhs: Self) {
+		*self = *self + rhs;
+	}
+}
+
+impl ops::Sub for Score {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self {
+		Self(self.0 - rhs.0, self.1 - rhs.1)
+	}
+}
+
+impl ops::Mul<i32> for Score {
+	type Output = Self;
+
+	fn mul(self, rhs: i32) -> Self {
+		Self(self.0 * rhs, self.1 * rhs)
+	}
+}
+
+const PIECES: [Piece; 6] = [
+	Piece::Pawn,
+	Piece::Knight,
+	Piece::Bishop,
+	Piece::Rook,
+	Piece::Queen,
+	Piece::King,
+];
+
+/// Piece values, indexed by piece
+const MATERIAL: [Score; 6] = [
+	Score(82, 94),
+	Score(337, 281),
+	Score(365, 297),
+	Score(477, 512),
+	Score(1025, 936),
+	Score(0, 0),
+];
+
+/// Bonus per square a piece attacks that isn't occupied by a friendly piece or
+/// attacked by an enemy pawn, indexed by piece
+const MOBILITY: [Score; 6] = [
+	Score(0, 0),
+	Score(4, 4),
+	Score(5, 5),
+	Score(2, 4),
+	Score(1, 2),
+	Score(0, 0),
+];
+
+/// Contribution of each piece to the game phase, indexed by piece
+const PHASE: [i32; 6] = [0, 1, 1, 2, 4, 0];
+/// The phase of the starting position, where only the middlegame score counts
+const MAX_PHASE: i32 = 24;
+
+const BISHOP_PAIR: Score = Score(30, 50);
+/// Penalty for each pawn on a file beyond the first
+const DOUBLED_PAWN: Score = Score(-10, -20);
+/// Penalty for each pawn without friendly pawns on adjacent files
+const ISOLATED_PAWN: Score = Score(-15, -10);
+/// Bonus for a pawn without enemy pawns in front of it or on adjacent files ahead
+/// of it, indexed by rank from the pawn's own side
+const PASSED_PAWN: [Score; 8] = [
+	Score(0, 0),
+	Score(5, 10),
+	Score(10, 15),
+	Score(15, 25),
+	Score(25, 45),
+	Score(40, 70),
+	Score(60, 110),
+	Score(0, 0),
+];
+/// Bonus for each pawn in the two ranks in front of the king
+const PAWN_SHIELD: Score = Score(10, 0);
+/// Bonus for each square next to the enemy king that a piece attacks
+const KING_ATTACK: Score = Score(8, 0);
+
+// Piece-square tables from white's point of view, with a8 in the top left
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+	 0,  0,   0,   0,   0,   0,  0,  0,
+	50, 50,  50,  50,  50,  50, 50, 50,
+	10, 10,  20,  30,  30,  20, 10, 10,
+	 5,  5,  10,  25,  25,  10,  5,  5,
+	 0,  0,   0,  20,  20,   0,  0,  0,
+	 5, -5, -10,   0,   0, -10, -5,  5,
+	 5, 10,  10, -20, -20,  10, 10,  5,
+	 0,  0,   0,   0,   0,   0,  0,  0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+	 0,  0,  0,  0,  0,  0,  0,  0,
+	80, 80, 80, 80, 80, 80, 80, 80,
+	50, 50, 50, 50, 50, 50, 50, 50,
+	30, 30, 30, 30, 30, 30, 30, 30,
+	15, 15, 15, 15, 15, 15, 15, 15,
+	 5,  5,  5,  5,  5,  5,  5,  5,
+	 0,  0,  0,  0,  0,  0,  0,  0,
+	 0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+	-50, -40, -30, -30, -30, -30, -40, -50,
+	-40, -20,   0,   0,   0,   0, -20, -40,
+	-30,   0,  10,  15,  15,  10,   0, -30,
+	-30,   5,  15,  20,  20,  15,   5, -30,
+	-30,   0,  15,  20,  20,  15,   0, -30,
+	-30,   5,  10,  15,  15,  10,   5, -30,
+	-40, -20,   0,   5,   5,   0, -20, -40,
+	-50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+	-20, -10, -10, -10, -10, -10, -10, -20,
+	-10,   0,   0,   0,   0,   0,   0, -10,
+	-10,   0,   5,  10,  10,   5,   0, -10,
+	-10,   5,   5,  10,  10,   5,   5, -10,
+	-10,   0,  10,  10,  10,  10,   0, -10,
+	-10,  10,  10,  10,  10,  10,  10, -10,
+	-10,   5,   0,   0,   0,   0,   5, -10,
+	-20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+	 0,  0,  0,  0,  0,  0,  0,  0,
+	 5, 10, 10, 10, 10, 10, 10,  5,
+	-5,  0,  0,  0,  0,  0,  0, -5,
+	-5,  0,  0,  0,  0,  0,  0, -5,
+	-5,  0,  0,  0,  0,  0,  0, -5,
+	-5,  0,  0,  0,  0,  0,  0, -5,
+	-5,  0,  0,  0,  0,  0,  0, -5,
+	 0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+	-20, -10, -10, -5, -5, -10, -10, -20,
+	-10,   0,   0,  0,  0,   0,   0, -10,
+	-10,   0,   5,  5,  5,   5,   0, -10,
+	 -5,   0,   5,  5,  5,   5,   0,  -5,
+	  0,   0,   5,  5,  5,   5,   0,  -5,
+	-10,   5,   5,  5,  5,   5,   0, -10,
+	-10,   0,   5,  0,  0,   0,   0, -10,
+	-20, -10, -10, -5, -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+	-30, -40, -40, -50, -50, -40, -40, -30,
+	-30, -40, -40, -50, -50, -40, -40, -30,
+	-30, -40, -40, -50, -50, -40, -40, -30,
+	-30, -40, -40, -50, -50, -40, -40, -30,
+	-20, -30, -30, -40, -40, -30, -30, -20,
+	-10, -20, -20, -20, -20, -20, -20, -10,
+	 20,  20,   0,   0,   0,   0,  20,  20,
+	 20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+	-50, -40, -30, -20, -20, -30, -40, -50,
+	-30, -20, -10,   0,   0, -10, -20, -30,
+	-30, -10,  20,  30,  30,  20, -10, -30,
+	-30, -10,  30,  40,  40,  30, -10, -30,
+	-30, -10,  30,  40,  40,  30, -10, -30,
+	-30, -10,  20,  30,  30,  20, -10, -30,
+	-30, -30,   0,   0,   0,   0, -30, -30,
+	-50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Middlegame and endgame piece-square tables, indexed by piece
+const PIECE_SQUARE: [(&[i32; 64], &[i32; 64]); 6] = [
+	(&PAWN_MG, &PAWN_EG),
+	(&KNIGHT, &KNIGHT),
+	(&BISHOP, &BISHOP),
+	(&ROOK, &ROOK),
+	(&QUEEN, &QUEEN),
+	(&KING_MG, &KING_EG),
+];
+
+/// Returns the rank of the given square counted from the player's side (0 to 7)
+fn relative_rank(pos: Pos, player: Player) -> u8 {
+	match player {
+		Player::White => pos.rank().value(),
+		Player::Black => 7 - pos.rank().value(),
+	}
+}
+
+/// Returns the squares on the given file and the files next to it
+fn files_around(file: u8) -> Bitboard {
+	let file = 0xff_u64 << (8 * file);
+	Bitboard::new(file | (file << 8) | (file >> 8))
+}
+
+/// Returns the squares on the same or adjacent files that are ahead of the given
+/// square from the player's point of view
+fn front_span(pos: Pos, player: Player) -> Bitboard {
+	let rank = pos.rank().value();
+	let ranks_ahead: u64 = match player {
+		Player::White => (0xff << (rank + 1)) & 0xff,
+		Player::Black => (1 << rank) - 1,
+	};
+	let files = files_around(pos.file().value());
+	Bitboard::new(files.value() & (ranks_ahead * 0x0101_0101_0101_0101))
+}
+
+/// Evaluates the position in centipawns from the point of view of the side to move
+pub(super) fn evaluate(board: &Board) -> i32 {
+	let score = evaluate_white(board);
+	match board.current_player() {
+		Player::White => score,
+		Player::Black => -score,
+	}
+}
+
+/// Evaluates the position in centipawns from white's point of view
+fn evaluate_white(board: &Board) -> i32 {
+	let score = evaluate_player(board, Player::White) - evaluate_player(board, Player::Black);
+	let phase = PIECES
+		.into_iter()
+		.map(|piece| {
+			let count = board.repr.pieces(Player::White, piece).count()
+				+ board.repr.pieces(Player::Black, piece).count();
+			PHASE[piece as usize] * count as i32
+		})
+		.sum::<i32>()
+		.min(MAX_PHASE);
+	(score.0 * phase + score.1 * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+fn evaluate_player(board: &Board, player: Player) -> Score {
+	let r = &board.repr;
+	let occupied = r.occupied();
+	let enemy_pawns = r.pieces(!player, Piece::Pawn);
+	let enemy_pawn_attacks = match player {
+		Player::White => enemy_pawns.black_pawn_attack_shifts(),
+		Player::Black => enemy_pawns.white_pawn_attack_shifts(),
+	};
+	let mobility_area = !(r.player_pieces(player) | enemy_pawn_attacks);
+	let enemy_king_zone = r.king(!player).map_or(Bitboard::empty(), Pos::adjacent);
+
+	let mut score = Score::default();
+	for piece in PIECES {
+		let (mg_table, eg_table) = PIECE_SQUARE[piece as usize];
+		for pos in r.pieces(player, piece) {
+			let square =
+				(7 - relative_rank(pos, player) as usize) * 8 + pos.file().value() as usize;
+			score += MATERIAL[piece as usize] + Score(mg_table[square], eg_table[square]);
+			let attacks = match piece {
+				Piece::Knight => pos.knight_moves(),
+				Piece::Bishop => pos.bishop_attacks(occupied),
+				Piece::Rook => pos.rook_attacks(occupied),
+				Piece::Queen => pos.queen_attacks(occupied),
+				Piece::Pawn | Piece::King => continue,
+			};
+			score += MOBILITY[piece as usize] * (attacks & mobility_area).count() as i32;
+			score += KING_ATTACK * (attacks & enemy_king_zone).count() as i32;
+		}
+	}
+	if r.pieces(player, Piece::Bishop).count() >= 2 {
+		score += BISHOP_PAIR;
+	}
+	score + pawn_structure(board, player) + king_safety(board, player)
+}
+
+/// Scores doubled, isolated and passed pawns
+fn pawn_structure(board: &Board, player: Player) -> Score {
+	let pawns = board.repr.pieces(player, Piece::Pawn);
+	let enemy_pawns = board.repr.pieces(!player, Piece::Pawn);
+	let mut score = Score::default();
+	for file in 0..8 {
+		let file_pawns = (pawns & Bitboard::new(0xff << (8 * file))).count() as i32;
+		if file_pawns > 1 {
+			score += DOUBLED_PAWN * (file_pawns - 1);
+		}
+		let neighbours = files_around(file) & !Bitboard::new(0xff << (8 * file));
+		if file_pawns > 0 && (pawns & neighbours).is_zero() {
+			score += ISOLATED_PAWN * file_pawns;
+		}
+	}
+	for pos in pawns {
+		if (enemy_pawns & front_span(pos, player)).is_zero() {
+			score += PASSED_PAWN[relative_rank(pos, player) as usize];
+		}
+	}
+	score
+}
+
+/// Rewards keeping pawns in front of the king
+fn king_safety(board: &Board, player: Player) -> Score {
+	let Some(king) = board.repr.king(player) else {
+		return Score::default();
+	};
+	let rank = king.rank().value();
+	let shield_ranks: u64 = match player {
+		Player::White => (0b110 << rank) & 0xff,
+		Player::Black => (0b011 << rank >> 2) & 0xff,
+	};
+	let shield = files_around(king.file().value()).value() & (shield_ranks * 0x0101_0101_0101_0101);
+	let pawns = board.repr.pieces(player, Piece::Pawn) & Bitboard::new(shield);
+	PAWN_SHIELD * pawns.count() as i32
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::BoardBuilder;
+
+	fn eval(fen: &str) -> i32 {
+		evaluate_white(&Board::from_fen(fen))
+	}
+
+	#[test]
+	fn mirror_symmetry() {
+		for fen in [
+			"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+			"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+			"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+			"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+			"r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+			"8/2k5/3p4/p2P1p2/P4P2/4K3/8/8 b - - 0 1",
+		] {
+			let board = Board::from_fen(fen);
+			let mirrored = BoardBuilder::from(board).mirror().build().unwrap();
+			assert_eq!(evaluate_white(&mirrored), -evaluate_white(&board), "{fen}");
+			assert_eq!(evaluate(&mirrored), evaluate(&board), "{fen}");
+		}
+	}
+
+	#[test]
+	fn terms() {
+		assert_eq!(
+			eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+			0
+		);
+
+		// an extra knight is worth roughly three pawns
+		let knight = eval("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+		assert!((250..450).contains(&knight), "{knight}");
+
+		// knights belong in the centre rather than on the rim
+		assert_eq!(
+			eval("r1bqkbnr/pppppppp/2n5/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1"),
+			0
+		);
+		assert!(eval("r1bqkbnr/pppppppp/n7/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1") > 0);
+
+		// a passed pawn is worth more than a blocked one
+		let passed = eval("4k3/p7/8/3P4/8/8/8/4K3 w - - 0 1");
+		let blocked = eval("4k3/2p5/8/3P4/8/8/8/4K3 w - - 0 1");
+		assert!(passed > blocked, "{passed} {blocked}");
+
+		// doubled and isolated pawns are weaknesses
+		assert!(
+			eval("4k3/8/8/8/8/3P4/3P4/4K3 w - - 0 1") < eval("4k3/8/8/8/8/8/2PP4/4K3 w - - 0 1")
+		);
+
+		// the bishop pair
+		assert!(eval("4k3/8/8/8/8/8/8/2BBK3 w - - 0 1") > eval("4k3/8/8/8/8/8/8/2BNK3 w - - 0 1"));
+
+		// a castled king behind its pawns is safer than one that has left them
+		let sheltered =
+			eval("r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 0 1");
+		let exposed =
+			eval("r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2NP1NP1/PPP2P1P/R1BQ1R1K w - - 0 1");
+		assert!(sheltered > exposed, "{sheltered} {exposed}");
+	}
+
+	#[test]
+	fn masks() {
+		let pos = |s| Pos::try_from(s).unwrap();
+		assert_eq!(files_around(0).value(), 0xffff);
+		assert_eq!(files_around(7).value(), 0xffff << 48);
+		let span = front_span(pos("e4"), Player::White);
+		assert!(span.get(pos("d5")) && span.get(pos("e8")) && span.get(pos("f7")));
+		assert!(!span.get(pos("e4")) && !span.get(pos("c5")) && !span.get(pos("d3")));
+		let span = front_span(pos("a7"), Player::Black);
+		assert!(span.get(pos("a1")) && span.get(pos("b6")) && !span.get(pos("b7")));
+	}
+}
@@ -0,0 +1,178 @@
+use crate::move_list::CAPACITY;
+use crate::{Board, Move, MoveList, Piece};
+use core::cmp::Reverse;
+
+/// Number of plies from the root for which killer moves are kept
+const MAX_PLY: usize = 128;
+
+const TT_MOVE_SCORE: i32 = 1_000_000;
+const GOOD_CAPTURE_SCORE: i32 = 100_000;
+const KILLER_SCORES: [i32; 2] = [90_000, 80_000];
+const BAD_CAPTURE_SCORE: i32 = -100_000;
+/// History scores are halved when one reaches this, keeping them below the killers
+const MAX_HISTORY: i32 = 50_000;
+
+/// Returns true if the move neither captures nor promotes
+pub(super) fn is_quiet(mov: Move) -> bool {
+	mov.captured_piece().is_none() && mov.promotion().is_none()
+}
+
+/// Most valuable victim, least valuable attacker: prefers capturing big pieces, and
+/// capturing them with small ones
+pub(super) fn mvv_lva(mov: Move) -> i32 {
+	let victim = mov.captured_piece().map_or(0, |piece| piece as i32 + 1);
+	let attacker = mov.moving_piece().map_or(0, |piece| piece as i32);
+	victim * 8 - attacker
+}
+
+/// Killer moves and history scores gathered while searching, used to order quiet
+/// moves that have caused cutoffs elsewhere in the tree
+pub(super) struct MoveOrdering {
+	/// Quiet moves that recently caused a cutoff at each ply
+	killers: [[Option<Move>; 2]; MAX_PLY],
+	/// Cutoff counts weighted by depth, indexed by from and to square
+	history: [[i32; 64]; 64],
+}
+
+impl MoveOrdering {
+	pub(super) fn new() -> Self {
+		Self {
+			killers: [[None; 2]; MAX_PLY],
+			history: [[0; 64]; 64],
+		}
+	}
+
+	/// Forgets the killers and ages the history scores, since the position has changed
+	pub(super) fn new_search(&mut self) {
+		self.killers = [[None; 2]; MAX_PLY];
+		for score in self.history.iter_mut().flatten() {
+			*score /= 8;
+		}
+	}
+
+	/// Records a quiet move that caused a beta cutoff
+	pub(super) fn record_cutoff(&mut self, mov: Move, ply: usize, depth: usize) {
+		if let Some(killers) = self.killers.get_mut(ply)
+			&& killers[0] != Some(mov)
+		{
+			killers[1] = killers[0];
+			killers[0] = Some(mov);
+		}
+		let depth = depth.min(100) as i32;
+		let score = &mut self.history[mov.from().value() as usize][mov.to().value() as usize];
+		*score += depth * depth;
+		if *score >= MAX_HISTORY {
+			for score in self.history.iter_mut().flatten() {
+				*score /= 2;
+			}
+		}
+	}
+
+	/// Scores a move for ordering, with higher scores searched first: the hash move,
+	/// then captures that don't lose material and queen promotions, then killers,
+	/// then the remaining quiet moves by history, and finally losing captures.
+	pub(super) fn score(&self, board: &Board, mov: Move, tt_move: Option<Move>, ply: usize) -> i32 {
+		if Some(mov) == tt_move {
+			return TT_MOVE_SCORE;
+		}
+		if !is_quiet(mov) {
+			let promotion = mov.promotion().map_or(0, Piece::value);
+			return if mov.promotion().is_some_and(|piece| piece != Piece::Queen) {
+				BAD_CAPTURE_SCORE + promotion
+			} else if board.see_ge(mov, 0) {
+				GOOD_CAPTURE_SCORE + promotion + mvv_lva(mov)
+			} else {
+				BAD_CAPTURE_SCORE + mvv_lva(mov)
+			};
+		}
+		if let Some(killers) = self.killers.get(ply)
+			&& let Some(i) = killers.iter().position(|&killer| killer == Some(mov))
+		{
+			return KILLER_SCORES[i];
+		}
+		self.history[mov.from().value() as usize][mov.to().value() as usize]
+	}
+}
+
+/// Yields moves from the highest score to the lowest. Each move is only selected
+/// when it is needed, since a cutoff often makes the remaining moves irrelevant.
+pub(super) struct MovePicker {
+	moves: MoveList,
+	scores: [i32; CAPACITY],
+	index: usize,
+}
+
+impl MovePicker {
+	pub(super) fn new(moves: MoveList, mut score: impl FnMut(Move) -> i32) -> Self {
+		let mut scores = [0; CAPACITY];
+		for (score_slot, &mov) in scores.iter_mut().zip(moves.iter()) {
+			*score_slot = score(mov);
+		}
+		Self {
+			moves,
+			scores,
+			index: 0,
+		}
+	}
+}
+
+impl Iterator for MovePicker {
+	type Item = Move;
+
+	fn next(&mut self) -> Option<Move> {
+		// the first of equally scored moves, so they stay in generation order
+		let remaining = self.index..self.moves.len();
+		let best = remaining.min_by_key(|&i| Reverse(self.scores[i]))?;
+		self.moves.swap(self.index, best);
+		self.scores.swap(self.index, best);
+		self.index += 1;
+		Some(self.moves[self.index - 1])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn mov(board: &Board, uci: &str) -> Move {
+		Move::parse_uci(board, uci).unwrap()
+	}
+
+	#[test]
+	fn order() {
+		// Nxb5 wins a bishop for a knight and Qxh5 wins a pawn, while taking the
+		// knight on d5 loses material
+		let board = Board::from_fen("4k3/8/2p5/1b1n3p/8/N7/8/3RK2Q w - - 0 1");
+		let mut ordering = MoveOrdering::new();
+		ordering.record_cutoff(mov(&board, "h1g2"), 2, 3);
+		ordering.record_cutoff(mov(&board, "e1f2"), 4, 3);
+		let tt_move = mov(&board, "a3c4");
+
+		let ordered: Vec<String> = MovePicker::new(board.legal_moves(), |m| {
+			ordering.score(&board, m, Some(tt_move), 2)
+		})
+		.map(|m| m.to_uci())
+		.collect();
+		assert_eq!(ordered.len(), board.legal_moves().len());
+		assert_eq!(ordered[..5], ["a3c4", "a3b5", "h1h5", "h1g2", "e1f2"]);
+		assert_eq!(ordered[ordered.len() - 2..], ["d1d5", "h1d5"]);
+	}
+
+	#[test]
+	fn killers() {
+		let board = Board::initial_position();
+		let mut ordering = MoveOrdering::new();
+		let [a, b, c] = ["e2e4", "d2d4", "g1f3"].map(|uci| mov(&board, uci));
+		ordering.record_cutoff(a, 0, 1);
+		ordering.record_cutoff(a, 0, 1);
+		ordering.record_cutoff(b, 0, 1);
+		assert_eq!(ordering.killers[0], [Some(b), Some(a)]);
+		ordering.record_cutoff(c, 0, 1);
+		assert_eq!(ordering.killers[0], [Some(c), Some(b)]);
+
+		// deep plies are ignored rather than panicking
+		ordering.record_cutoff(c, MAX_PLY, 1);
+		ordering.new_search();
+		assert_eq!(ordering.killers[0], [None; 2]);
+	}
+}
@@ -0,0 +1,149 @@
+use crate::Move;
+
+/// How a stored score relates to the position's true score
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Bound {
+	/// The score is exact
+	Exact,
+	/// The search failed high, so the true score is at least this
+	Lower,
+	/// The search failed low, so the true score is at most this
+	Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Entry {
+	key: u64,
+	pub(crate) best_move: Option<Move>,
+	pub(crate) score: i32,
+	pub(crate) depth: u8,
+	pub(crate) bound: Bound,
+	generation: u8,
+}
+
+/// A fixed-size hash table of search results, indexed by Zobrist key
+pub(crate) struct TranspositionTable {
+	entries: Vec<Option<Entry>>,
+	/// Incremented for each search, so that entries from earlier searches are
+	/// replaced first
+	generation: u8,
+}
+
+impl TranspositionTable {
+	/// Constructs a table using at most the given number of megabytes. A size of 0
+	/// disables the table.
+	pub(crate) fn new(size_mb: usize) -> Self {
+		let len = size_mb * 1024 * 1024 / size_of::<Option<Entry>>();
+		Self {
+			entries: vec![None; len],
+			generation: 0,
+		}
+	}
+
+	/// Marks the start of a new search
+	pub(crate) fn new_search(&mut self) {
+		self.generation = self.generation.wrapping_add(1);
+	}
+
+	pub(crate) fn clear(&mut self) {
+		self.entries.fill(None);
+	}
+
+	fn index(&self, key: u64) -> Option<usize> {
+		if self.entries.is_empty() {
+			return None;
+		}
+		Some((key % self.entries.len() as u64) as usize)
+	}
+
+	pub(crate) fn probe(&self, key: u64) -> Option<Entry> {
+		self.entries[self.index(key)?].filter(|entry| entry.key == key)
+	}
+
+	/// Stores a search result. An existing entry for a different position is only
+	/// replaced if it is from an earlier search or was searched less deeply.
+	pub(crate) fn store(
+		&mut self,
+		key: u64,
+		depth: usize,
+		bound: Bound,
+		score: i32,
+		best_move: Option<Move>,
+	) {
+		let Some(index) = self.index(key) else {
+			return;
+		};
+		let depth = depth.min(u8::MAX as usize) as u8;
+		let slot = &mut self.entries[index];
+		let mut best_move = best_move;
+		if let Some(old) = slot {
+			if old.key == key {
+				// keep the old move if this search didn't find one
+				best_move = best_move.or(old.best_move);
+			} else if old.generation == self.generation && old.depth > depth {
+				return;
+			}
+		}
+		*slot = Some(Entry {
+			key,
+			best_move,
+			score,
+			depth,
+			bound,
+			generation: self.generation,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::*;
+
+	#[test]
+	fn store_and_probe() {
+		let mut tt = TranspositionTable::new(1);
+		assert!(tt.entries.len() > 1000);
+		let e4 = Move::from_uci("e2e4");
+		tt.store(42, 3, Bound::Lower, 17, Some(e4));
+		let entry = tt.probe(42).unwrap();
+		assert_eq!(
+			(entry.depth, entry.bound, entry.score, entry.best_move),
+			(3, Bound::Lower, 17, Some(e4))
+		);
+		assert_eq!(tt.probe(43), None);
+
+		// a different position in the same slot
+		let other = 42 + tt.entries.len() as u64;
+		assert_eq!(tt.probe(other), None);
+
+		tt.store(42, 2, Bound::Upper, -5, None);
+		let entry = tt.probe(42).unwrap();
+		assert_eq!((entry.depth, entry.best_move), (2, Some(e4)));
+
+		tt.clear();
+		assert_eq!(tt.probe(42), None);
+
+		let mut disabled = TranspositionTable::new(0);
+		disabled.store(42, 3, Bound::Exact, 0, None);
+		assert_eq!(disabled.probe(42), None);
+	}
+
+	#[test]
+	fn replacement() {
+		let mut tt = TranspositionTable::new(1);
+		let other = 42 + tt.entries.len() as u64;
+		tt.store(42, 5, Bound::Exact, 1, None);
+		tt.store(other, 3, Bound::Exact, 2, None);
+		assert_eq!(tt.probe(42).unwrap().score, 1);
+		assert_eq!(tt.probe(other), None);
+
+		tt.store(other, 5, Bound::Exact, 2, None);
+		assert_eq!(tt.probe(other).unwrap().score, 2);
+
+		// entries from earlier searches are always replaced
+		tt.new_search();
+		tt.store(42, 1, Bound::Exact, 3, None);
+		assert_eq!(tt.probe(42).unwrap().score, 3);
+	}
+}
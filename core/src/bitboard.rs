@@ -16,6 +16,11 @@ impl Bitboard {
 		Self { value }
 	}
 
+	/// Returns the underlying u64
+	pub fn value(&self) -> u64 {
+		self.value
+	}
+
 	pub fn single_bit(pos: Pos) -> Self {
 		Self::new(1 << pos.value())
 	}
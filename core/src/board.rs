@@ -1,160 +1,82 @@
 #[cfg(test)]
 mod tests;
 
+mod attacks;
+mod builder;
+mod fen;
+mod movegen;
 mod repr;
+mod see;
+mod validate;
+mod zobrist;
 
+use crate::mov::MoveFlags;
 use crate::*;
-use core::{fmt, ops};
+pub use builder::BoardBuilder;
+use core::{fmt, hash, ops};
+pub use fen::{FenError, FenField};
 use repr::Repr;
+pub use validate::PositionError;
 
-#[derive(Debug, Copy, Clone)]
+/// Squares of the same colour as a1
+const DARK_SQUARES: u64 = 0xaa55_aa55_aa55_aa55;
+
+/// The state [`Board::make_move`] cannot recover from the move alone, used by
+/// [`Board::unmake_move`] to take the move back.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UndoInfo {
+	captured: Option<Piece>,
+	castling_rook: Option<Pos>,
+	castling_rights: [Option<File>; 4],
+	en_passant_target: Option<Pos>,
+	halfmove_clock: u32,
+	zobrist_key: u64,
+}
+
+impl UndoInfo {
+	/// The piece captured by the move, if any
+	pub fn captured(&self) -> Option<Piece> {
+		self.captured
+	}
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Board {
-	pub current_player: Player,
+	current_player: Player,
 	en_passant_target: Option<Pos>,
-	pub repr: Repr,
-	white_kingside_castle: bool,
-	white_queenside_castle: bool,
-	black_kingside_castle: bool,
-	black_queenside_castle: bool,
+	pub(crate) repr: Repr,
+	/// Castling rights, stored as the file of the rook that may castle
+	white_kingside_castle: Option<File>,
+	white_queenside_castle: Option<File>,
+	black_kingside_castle: Option<File>,
+	black_queenside_castle: Option<File>,
+	halfmove_clock: u32,
+	fullmove_number: u32,
+	/// Incrementally updated Zobrist key of this position
+	zobrist_key: u64,
 }
 
 impl Board {
 	pub fn empty() -> Self {
-		Self {
+		let mut board = Self {
 			current_player: Player::White,
 			en_passant_target: None,
 			repr: Repr::empty(),
-			white_kingside_castle: true,
-			white_queenside_castle: true,
-			black_kingside_castle: true,
-			black_queenside_castle: true,
-		}
-	}
-
-	pub fn from_fen(fen: &str) -> Self {
-		let mut result = Self::empty();
-		result.white_kingside_castle = false;
-		result.white_queenside_castle = false;
-		result.black_kingside_castle = false;
-		result.black_queenside_castle = false;
-		let mut fen_iter = fen.chars();
-		let mut pos: Option<Pos> = None;
-		let mut next = || {
-			pos = Some(if let Some(pos) = pos {
-				if let Some(x) = pos.offset(Direction::E) {
-					x
-				} else {
-					Pos::new(File::A, pos.rank().prev().expect("invalid fen"))
-				}
-			} else {
-				Pos::new(File::A, Rank::Eight)
-			});
-			pos.unwrap()
-		};
-		for ch in fen_iter.by_ref() {
-			match ch {
-				' ' => break,
-				'1'..='8' => {
-					let n = ch as u8 - b'0';
-					for _ in 0..n {
-						result.setp(next(), None);
-					}
-				}
-				'/' => (),
-				_ => {
-					let piece = Piece::from_ascii_char(ch);
-					result.setp(next(), Some(piece));
-				}
-			}
-		}
-		result.current_player = match fen_iter.next() {
-			Some('w') => Player::White,
-			Some('b') => Player::Black,
-			_ => panic!("invalid fen"),
+			white_kingside_castle: None,
+			white_queenside_castle: None,
+			black_kingside_castle: None,
+			black_queenside_castle: None,
+			halfmove_clock: 0,
+			fullmove_number: 1,
+			zobrist_key: 0,
 		};
-		assert_eq!(fen_iter.next().unwrap(), ' ');
-		while let Some(ch) = fen_iter.next() {
-			match ch {
-				'-' => {
-					assert_eq!(fen_iter.next().unwrap(), ' ');
-					break;
-				}
-				'K' => result.white_kingside_castle = true,
-				'Q' => result.white_queenside_castle = true,
-				'k' => result.black_kingside_castle = true,
-				'q' => result.black_queenside_castle = true,
-				' ' => break,
-				_ => panic!("invalid fen"),
-			}
-		}
-		match fen_iter.next().unwrap() {
-			'-' => (),
-			file @ 'a'..='h' => {
-				let rank = fen_iter.next().unwrap();
-				result.en_passant_target = Some(Pos::new(
-					File::from_value(file as u8 - b'a'),
-					Rank::from_value(rank as u8 - b'1'),
-				));
-			}
-			_ => panic!("invalid fen"),
-		}
-		// TODO: halfmove clock and fullmove number
-		result
+		board.zobrist_key = board.compute_zobrist_key();
+		board
 	}
 
-	pub fn to_fen(&self) -> String {
-		let mut result = String::new();
-		for rank in (0..8).rev() {
-			let mut empty = 0;
-			for file in 'a'..='h' {
-				let pos = Pos::new(File::from_value(file as u8 - b'a'), Rank::from_value(rank));
-				if let Some((player, piece)) = self.getp(pos) {
-					if empty > 0 {
-						result.push((b'0' + empty) as char);
-						empty = 0;
-					}
-					result.push(piece.ascii_char(player));
-				} else {
-					empty += 1;
-				}
-			}
-			if empty > 0 {
-				result.push((b'0' + empty) as char);
-			}
-			if rank > 0 {
-				result.push('/');
-			}
-		}
-		result.push(' ');
-		result.push(match self.current_player {
-			Player::White => 'w',
-			Player::Black => 'b',
-		});
-		result.push(' ');
-		if self.white_kingside_castle {
-			result.push('K');
-		}
-		if self.white_queenside_castle {
-			result.push('Q');
-		}
-		if self.black_kingside_castle {
-			result.push('k');
-		}
-		if self.black_queenside_castle {
-			result.push('q');
-		}
-		if result.ends_with(' ') {
-			result.push('-');
-		}
-		result.push(' ');
-		if let Some(pos) = self.en_passant_target {
-			result.push(pos.file().into());
-			result.push(pos.rank().into());
-		} else {
-			result.push('-');
-		}
-		// TODO: halfmove clock and fullmove number
-		result
+	/// Parses a FEN string, panicking if it is invalid. See [`Board::try_from_fen`].
+	pub fn from_fen(fen: &str) -> Self {
+		Self::try_from_fen(fen).unwrap_or_else(|e| panic!("{e}"))
 	}
 
 	pub fn initial_position() -> Self {
@@ -169,277 +91,352 @@ impl Board {
 				.set(i * 8 + 6, Some((Player::Black, Piece::Pawn)));
 			board.repr.set(i * 8 + 7, Some((Player::Black, piece)));
 		}
+		board.white_kingside_castle = Some(File::H);
+		board.white_queenside_castle = Some(File::A);
+		board.black_kingside_castle = Some(File::H);
+		board.black_queenside_castle = Some(File::A);
+		board.zobrist_key = board.compute_zobrist_key();
 		board
 	}
 
-	/// Returns all possible moves for the given piece, ignoring checks.
-	fn simple_piece_moves(&self, pos: Pos, en_passant_target: Option<Pos>) -> Bitboard {
-		let (player, piece) = self.getp(pos).expect("no piece at position");
-		let iterator = match piece {
-			Piece::Pawn => {
-				let mut result = Bitboard::empty();
-				let (direction, starting_rank, capture_dirs) = match player {
-					Player::White => (Direction::N, Rank::Two, [Direction::NW, Direction::NE]),
-					Player::Black => (Direction::S, Rank::Seven, [Direction::SW, Direction::SE]),
-				};
-				let forward_one = pos.offset(direction).expect("pawn at far edge");
-				if self.getp(forward_one).is_none() {
-					result.set(forward_one);
-					if pos.rank() == starting_rank {
-						let forward_two = forward_one.offset(direction).expect("invalid pawn");
-						if self.getp(forward_two).is_none() {
-							result.set(forward_two);
-						}
-					}
-				}
-				for direction in capture_dirs {
-					let Some(target_pos) = pos.offset(direction) else {
-						continue;
-					};
-					let Some((target_player, _)) = self.getp(target_pos) else {
-						continue;
-					};
-					if target_player == player {
-						continue;
-					}
-					result.set(target_pos);
-				}
-				if let Some(en_passant_target) = en_passant_target {
-					if pos.offset(capture_dirs[0]) == Some(en_passant_target)
-						|| pos.offset(capture_dirs[1]) == Some(en_passant_target)
-					{
-						result.set(en_passant_target);
-					}
+	/// Returns the Chess960 starting position with the given index (0 to 959), using
+	/// Scharnagl's numbering. Index 518 is the standard starting position.
+	pub fn chess960_position(index: u16) -> Self {
+		assert!(
+			index < 960,
+			"{index} is not a valid Chess960 position index"
+		);
+		let mut home_row = [None; 8];
+		let mut n = index as usize;
+		home_row[(n % 4) * 2 + 1] = Some(Piece::Bishop);
+		n /= 4;
+		home_row[(n % 4) * 2] = Some(Piece::Bishop);
+		n /= 4;
+		let mut place = |piece, mut nth_empty: usize| {
+			for square in home_row.iter_mut().filter(|square| square.is_none()) {
+				if nth_empty == 0 {
+					*square = Some(piece);
+					return;
 				}
-				return result;
-			}
-			Piece::Knight => {
-				return !self.repr.player_pieces(player) & pos.knight_moves();
-			}
-			Piece::Bishop => DIAGONAL_DIRECTIONS.iter(),
-			Piece::Rook => ORTHOGONAL_DIRECTIONS.iter(),
-			Piece::Queen => ADJACENT_DIRECTIONS.iter(),
-			Piece::King => {
-				return !self.repr.player_pieces(player) & pos.adjacent();
+				nth_empty -= 1;
 			}
 		};
-		let mut result = Bitboard::empty();
-		for direction in iterator.copied() {
-			let mut current = pos;
-			loop {
-				current = match current.offset(direction) {
-					Some(current) => current,
-					None => break,
-				};
-				if let Some((target_player, _)) = self.getp(current) {
-					if target_player == player {
-						// own piece
-						break;
-					} else {
-						// opponent piece
-						result.set(current);
-						break;
-					}
-				}
-				result.set(current);
-			}
+		place(Piece::Queen, n % 6);
+		n /= 6;
+		let (first, second) = [
+			(0, 1),
+			(0, 2),
+			(0, 3),
+			(0, 4),
+			(1, 2),
+			(1, 3),
+			(1, 4),
+			(2, 3),
+			(2, 4),
+			(3, 4),
+		][n];
+		// the second knight goes on the remaining empty squares after the first
+		place(Piece::Knight, second);
+		place(Piece::Knight, first);
+		for piece in [Piece::Rook, Piece::King, Piece::Rook] {
+			place(piece, 0);
 		}
-		result
-	}
 
-	fn square_in_check(&self, king_pos: Pos) -> bool {
-		let x = match self.current_player {
-			Player::White => {
-				self.repr.black_knights.knight_shifts()
-					| self.repr.black_pawns.black_pawn_attack_shifts()
-					| self.repr.king_pos(Player::Black).adjacent()
-			}
-			Player::Black => {
-				self.repr.white_knights.knight_shifts()
-					| self.repr.white_pawns.white_pawn_attack_shifts()
-					| self.repr.king_pos(Player::White).adjacent()
-			}
-		};
-		if !(x & Bitboard::single_bit(king_pos)).is_zero() {
-			return true;
-		}
-		for pos in (king_pos.bishop_moves()
-			& self.repr.player_pieces_checks_1(!self.current_player))
-			| (king_pos.rook_moves() & self.repr.player_pieces_checks_2(!self.current_player))
-		{
-			if self.simple_piece_moves(pos, None).get(king_pos) {
-				return true;
+		let mut board = Self::empty();
+		let mut rook_files = vec![];
+		for (file, piece) in FILES.into_iter().zip(home_row) {
+			let piece = piece.expect("home row is not full");
+			board.setp(Pos::new(file, Rank::One), Some((Player::White, piece)));
+			board.setp(
+				Pos::new(file, Rank::Two),
+				Some((Player::White, Piece::Pawn)),
+			);
+			board.setp(
+				Pos::new(file, Rank::Seven),
+				Some((Player::Black, Piece::Pawn)),
+			);
+			board.setp(Pos::new(file, Rank::Eight), Some((Player::Black, piece)));
+			if piece == Piece::Rook {
+				rook_files.push(file);
 			}
 		}
-		false
+		board.white_queenside_castle = Some(rook_files[0]);
+		board.black_queenside_castle = Some(rook_files[0]);
+		board.white_kingside_castle = Some(rook_files[1]);
+		board.black_kingside_castle = Some(rook_files[1]);
+		board.zobrist_key = board.compute_zobrist_key();
+		board
 	}
 
 	pub fn in_check(&self) -> bool {
-		let king_pos = self.repr.king_pos(self.current_player);
-		self.square_in_check(king_pos)
+		!self.checkers().is_zero()
 	}
 
-	pub fn all_moves(&self, mut add_move: impl FnMut(Move) -> ops::ControlFlow<()>) {
-		let king_pos = self.repr.king_pos(self.current_player);
-		for pos in self.repr.player_pieces(self.current_player) {
-			let original_piece = self.getp(pos).unwrap().1;
-			let targets = self.simple_piece_moves(pos, self.en_passant_target);
-			for target in targets {
-				let mut new_board = *self;
-				new_board.setp(pos, None);
-				new_board.setp(target, Some((self.current_player, original_piece)));
-				if new_board.square_in_check(if original_piece == Piece::King {
-					target
-				} else {
-					king_pos
-				}) {
-					continue;
-				}
-				if original_piece == Piece::Pawn
-					&& (target.rank() == Rank::Eight || target.rank() == Rank::One)
-				{
-					for promotion in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
-						if add_move(Move {
-							from: pos,
-							to: target,
-							promotion: Some(promotion),
-						})
-						.is_break()
-						{
-							return;
-						}
-					}
-				} else if add_move(Move {
-					from: pos,
-					to: target,
-					promotion: None,
-				})
-				.is_break()
-				{
-					return;
-				}
-			}
+	/// Returns the files of the rooks the given player may castle with on the
+	/// kingside and queenside.
+	fn castling_rights(&self, player: Player) -> (Option<File>, Option<File>) {
+		match player {
+			Player::White => (self.white_kingside_castle, self.white_queenside_castle),
+			Player::Black => (self.black_kingside_castle, self.black_queenside_castle),
 		}
-		let (kingside_castle, queenside_castle, rank) = match self.current_player {
-			Player::White => (
-				self.white_kingside_castle,
-				self.white_queenside_castle,
-				Rank::One,
-			),
-			Player::Black => (
-				self.black_kingside_castle,
-				self.black_queenside_castle,
-				Rank::Eight,
-			),
+	}
+
+	/// If the given move is castling, returns the position of the castling rook.
+	/// Castling is written either as the king moving to its destination (in standard
+	/// chess) or as the king capturing its own rook (in Chess960).
+	pub(crate) fn castling_rook(&self, mov: Move) -> Option<Pos> {
+		if mov.moving_piece().is_some() && mov.castling_side().is_none() {
+			return None;
+		}
+		let (player, piece) = self.getp(mov.from())?;
+		if piece != Piece::King || mov.from().rank() != mov.to().rank() {
+			return None;
+		}
+		if self.getp(mov.to()) == Some((player, Piece::Rook)) {
+			return Some(mov.to());
+		}
+		let (kingside_castle, queenside_castle) = self.castling_rights(player);
+		let rook_file = match (mov.from().file(), mov.to().file()) {
+			(File::E, File::G) => kingside_castle?,
+			(File::E, File::C) => queenside_castle?,
+			_ => return None,
 		};
-		if kingside_castle
-			&& self.getp(Pos::new(File::F, rank)).is_none()
-			&& self.getp(Pos::new(File::G, rank)).is_none()
-			&& match self.current_player {
-				Player::White => self.repr.white_rooks.get(Pos::new(File::H, Rank::One)),
-				Player::Black => self.repr.black_rooks.get(Pos::new(File::H, Rank::Eight)),
-			} && !self.square_in_check(Pos::new(File::E, rank))
-			&& !self.square_in_check(Pos::new(File::F, rank))
-			&& !self.square_in_check(Pos::new(File::G, rank))
-			&& add_move(Move {
-				from: Pos::new(File::E, rank),
-				to: Pos::new(File::G, rank),
-				promotion: None,
-			})
-			.is_break()
-		{
-			return;
+		Some(Pos::new(rook_file, mov.from().rank()))
+	}
+
+	/// Returns the given move with its flags (moving and captured piece, castling,
+	/// en passant and double pawn push) filled in for this position. Moves that
+	/// already have flags, such as those from [`Board::all_moves`], are returned
+	/// unchanged.
+	pub fn annotate(&self, mov: Move) -> Move {
+		if mov.moving_piece().is_some() {
+			return mov;
 		}
-		if queenside_castle
-			&& self.getp(Pos::new(File::D, rank)).is_none()
-			&& self.getp(Pos::new(File::C, rank)).is_none()
-			&& self.getp(Pos::new(File::B, rank)).is_none()
-			&& match self.current_player {
-				Player::White => self.repr.white_rooks.get(Pos::new(File::A, Rank::One)),
-				Player::Black => self.repr.black_rooks.get(Pos::new(File::A, Rank::Eight)),
-			} && !self.square_in_check(Pos::new(File::E, rank))
-			&& !self.square_in_check(Pos::new(File::D, rank))
-			&& !self.square_in_check(Pos::new(File::C, rank))
-			&& add_move(Move {
-				from: Pos::new(File::E, rank),
-				to: Pos::new(File::C, rank),
-				promotion: None,
+		let Some((_, piece)) = self.getp(mov.from()) else {
+			return mov;
+		};
+		let flags = if let Some(rook) = self.castling_rook(mov) {
+			MoveFlags::castling(if rook.file() > mov.from().file() {
+				CastlingSide::Kingside
+			} else {
+				CastlingSide::Queenside
 			})
-			.is_break()
-		{}
+		} else if piece == Piece::Pawn
+			&& Some(mov.to()) == self.en_passant_target
+			&& mov.from().file() != mov.to().file()
+		{
+			MoveFlags::en_passant()
+		} else if piece == Piece::Pawn
+			&& mov.to().rank().value().abs_diff(mov.from().rank().value()) == 2
+		{
+			MoveFlags::double_push()
+		} else {
+			MoveFlags::new(piece, self.getp(mov.to()).map(|(_, piece)| piece))
+		};
+		mov.with_flags(flags)
 	}
 
 	pub fn apply_move(&mut self, mov: Move) {
-		let (player, piece) = self.getp(mov.from).expect("no piece at from");
-		self.setp(mov.to, None);
-		if piece == Piece::King {
-			match player {
-				Player::White => self.repr.white_king = mov.to,
-				Player::Black => self.repr.black_king = mov.to,
-			}
+		let mov = self.annotate(mov);
+		let (player, piece) = self.getp(mov.from()).expect("no piece at from");
+		let castling_rook = self.castling_rook(mov);
+		if piece == Piece::Pawn || mov.captured_piece().is_some() {
+			self.halfmove_clock = 0;
 		} else {
-			let b = self.repr.mut_bitboard((player, piece));
-			b.clear(mov.from);
-			match mov.promotion {
-				None => b.set(mov.to),
-				Some(promotion) => {
-					let pb = self.repr.mut_bitboard((player, promotion));
-					pb.set(mov.to);
-				}
-			}
+			self.halfmove_clock += 1;
+		}
+		if player == Player::Black {
+			self.fullmove_number += 1;
+		}
+		if let Some(rook_pos) = castling_rook {
+			let (king_to, rook_to) = if rook_pos.file() > mov.from().file() {
+				(File::G, File::F)
+			} else {
+				(File::C, File::D)
+			};
+			self.setp(rook_pos, None);
+			self.setp(
+				Pos::new(king_to, mov.from().rank()),
+				Some((player, Piece::King)),
+			);
+			self.setp(
+				Pos::new(rook_to, mov.from().rank()),
+				Some((player, Piece::Rook)),
+			);
+		} else {
+			// placing the piece also removes any captured piece
+			self.setp(mov.from(), None);
+			self.setp(mov.to(), Some((player, mov.promotion().unwrap_or(piece))));
 		}
 		let back_dir = match player {
 			Player::White => Direction::S,
 			Player::Black => Direction::N,
 		};
-		if piece == Piece::Pawn && Some(mov.to) == self.en_passant_target {
-			let capture_pos = mov.to.offset(back_dir).expect("invalid en passant move");
+		if mov.is_en_passant() {
+			let capture_pos = mov.to().offset(back_dir).expect("invalid en passant move");
 			assert!(self.getp(capture_pos) == Some((!player, Piece::Pawn)));
-			self.repr.black_pawns.clear(capture_pos);
-			self.repr.white_pawns.clear(capture_pos);
+			self.setp(capture_pos, None);
 		}
-		if piece == Piece::Pawn && mov.to.value().abs_diff(mov.from.value()) == 2 {
-			self.en_passant_target = Some(mov.to.offset(back_dir).expect("invalid pawn move"));
+		self.zobrist_key ^= self.castling_key() ^ self.en_passant_key() ^ self.turn_key();
+		if mov.is_double_push() {
+			self.en_passant_target = Some(mov.to().offset(back_dir).expect("invalid pawn move"));
 		} else {
 			self.en_passant_target = None;
 		}
-		if piece == Piece::King {
-			if mov.from.file() == File::E && mov.to.file() == File::G {
-				let rook_pos = Pos::new(File::H, mov.from.rank());
-				let (player, piece) = self.getp(rook_pos).expect("no rook on h1");
-				assert!(player == self.current_player && piece == Piece::Rook);
-				let b = self.repr.mut_bitboard((player, Piece::Rook));
-				b.clear(rook_pos);
-				b.set(Pos::new(File::F, mov.from.rank()));
-			} else if mov.from.file() == File::E && mov.to.file() == File::C {
-				let rook_pos = Pos::new(File::A, mov.from.rank());
-				let (player, piece) = self.getp(rook_pos).expect("no rook on a1");
-				assert!(player == self.current_player && piece == Piece::Rook);
-				let b = self.repr.mut_bitboard((player, Piece::Rook));
-				b.clear(rook_pos);
-				b.set(Pos::new(File::D, mov.from.rank()));
-			}
-		}
 		self.current_player = !self.current_player;
 		if (player, piece) == (Player::White, Piece::King) {
-			self.white_kingside_castle = false;
-			self.white_queenside_castle = false;
+			self.white_kingside_castle = None;
+			self.white_queenside_castle = None;
 		} else if (player, piece) == (Player::Black, Piece::King) {
-			self.black_kingside_castle = false;
-			self.black_queenside_castle = false;
-		} else if (player, piece) == (Player::White, Piece::Rook) {
-			if mov.from == Pos::new(File::A, Rank::One) {
-				self.white_queenside_castle = false;
-			} else if mov.from == Pos::new(File::H, Rank::One) {
-				self.white_kingside_castle = false;
+			self.black_kingside_castle = None;
+			self.black_queenside_castle = None;
+		}
+		// moving or capturing a rook loses the corresponding castling right
+		for pos in [mov.from(), mov.to()] {
+			let file = Some(pos.file());
+			let rights = match pos.rank() {
+				Rank::One => [
+					&mut self.white_kingside_castle,
+					&mut self.white_queenside_castle,
+				],
+				Rank::Eight => [
+					&mut self.black_kingside_castle,
+					&mut self.black_queenside_castle,
+				],
+				_ => continue,
+			};
+			for right in rights {
+				if *right == file {
+					*right = None;
+				}
 			}
-		} else if (player, piece) == (Player::Black, Piece::Rook) {
-			if mov.from == Pos::new(File::A, Rank::Eight) {
-				self.black_queenside_castle = false;
-			} else if mov.from == Pos::new(File::H, Rank::Eight) {
-				self.black_kingside_castle = false;
+		}
+		self.zobrist_key ^= self.castling_key() ^ self.en_passant_key() ^ self.turn_key();
+		debug_assert_eq!(self.zobrist_key, self.compute_zobrist_key());
+	}
+
+	/// Plays the given move, which must be legal, returning the information needed
+	/// to take it back with [`Board::unmake_move`].
+	pub fn make_move(&mut self, mov: Move) -> UndoInfo {
+		let mov = self.annotate(mov);
+		let castling_rook = self.castling_rook(mov);
+		let undo = UndoInfo {
+			captured: mov.captured_piece(),
+			castling_rook,
+			castling_rights: [
+				self.white_kingside_castle,
+				self.white_queenside_castle,
+				self.black_kingside_castle,
+				self.black_queenside_castle,
+			],
+			en_passant_target: self.en_passant_target,
+			halfmove_clock: self.halfmove_clock,
+			zobrist_key: self.zobrist_key,
+		};
+		self.apply_move(mov);
+		undo
+	}
+
+	/// Takes back a move played with [`Board::make_move`], restoring the position
+	/// exactly as it was before.
+	pub fn unmake_move(&mut self, mov: Move, undo: UndoInfo) {
+		self.current_player = !self.current_player;
+		let player = self.current_player;
+		if player == Player::Black {
+			self.fullmove_number -= 1;
+		}
+		[
+			self.white_kingside_castle,
+			self.white_queenside_castle,
+			self.black_kingside_castle,
+			self.black_queenside_castle,
+		] = undo.castling_rights;
+		self.en_passant_target = undo.en_passant_target;
+		self.halfmove_clock = undo.halfmove_clock;
+		self.zobrist_key = undo.zobrist_key;
+
+		let from = mov.from().value() as usize;
+		let to = mov.to().value() as usize;
+		if let Some(rook) = undo.castling_rook {
+			let rook_to = if rook.file() > mov.from().file() {
+				File::F
+			} else {
+				File::D
+			};
+			// the rook is placed first, as it may start on the king's destination
+			self.repr
+				.set(Pos::new(rook_to, rook.rank()).value() as usize, None);
+			self.repr
+				.set(rook.value() as usize, Some((player, Piece::Rook)));
+			self.repr.set(from, Some((player, Piece::King)));
+			return;
+		}
+		let (_, piece) = self.repr.get(to).expect("no piece at to");
+		let piece = if mov.promotion().is_some() {
+			Piece::Pawn
+		} else {
+			piece
+		};
+		self.repr.set(to, None);
+		self.repr.set(from, Some((player, piece)));
+		match undo.captured {
+			Some(Piece::Pawn)
+				if piece == Piece::Pawn && Some(mov.to()) == undo.en_passant_target =>
+			{
+				let captured = Pos::new(mov.to().file(), mov.from().rank());
+				self.repr
+					.set(captured.value() as usize, Some((!player, Piece::Pawn)));
 			}
+			Some(captured) => self.repr.set(to, Some((!player, captured))),
+			None => (),
+		}
+	}
+
+	/// Returns the en passant target only if a pawn of the current player could capture
+	/// onto it, ignoring pins. This matches the FIDE definition of a repeated position.
+	fn en_passant_capture(&self) -> Option<Pos> {
+		let target = self.en_passant_target?;
+		let attacks = match self.current_player {
+			Player::White => self.repr.white_pawns.white_pawn_attack_shifts(),
+			Player::Black => self.repr.black_pawns.black_pawn_attack_shifts(),
+		};
+		attacks.get(target).then_some(target)
+	}
+
+	/// Returns true if both boards represent the same position for the purpose of
+	/// repetition detection (same pieces, side to move, castling and en passant rights).
+	pub fn same_position(&self, other: &Board) -> bool {
+		self.zobrist_key == other.zobrist_key
+			&& self.repr == other.repr
+			&& self.current_player == other.current_player
+			&& self.white_kingside_castle == other.white_kingside_castle
+			&& self.white_queenside_castle == other.white_queenside_castle
+			&& self.black_kingside_castle == other.black_kingside_castle
+			&& self.black_queenside_castle == other.black_queenside_castle
+			&& self.en_passant_capture() == other.en_passant_capture()
+	}
+
+	/// Returns true if neither player can possibly checkmate, i.e. if the position is
+	/// K vs K, K+minor piece vs K, or if all remaining bishops are on the same colour.
+	pub fn has_insufficient_material(&self) -> bool {
+		let r = &self.repr;
+		let heavy_pieces = r.white_pawns
+			| r.black_pawns
+			| r.white_rooks
+			| r.black_rooks
+			| r.white_queens
+			| r.black_queens;
+		if !heavy_pieces.is_zero() {
+			return false;
+		}
+		let knights = r.white_knights | r.black_knights;
+		let bishops = r.white_bishops | r.black_bishops;
+		if (knights | bishops).count() <= 1 {
+			return true;
 		}
+		let dark_squares = Bitboard::new(DARK_SQUARES);
+		knights.is_zero()
+			&& ((bishops & dark_squares).is_zero() || (bishops & !dark_squares).is_zero())
 	}
 
 	pub fn game_result(&self) -> Option<GameResult> {
@@ -449,7 +446,10 @@ impl Board {
 			ops::ControlFlow::Break(())
 		});
 		if any_moves {
-			None
+			self.has_insufficient_material()
+				.then_some(GameResult::Draw {
+					draw: DrawReason::InsufficientMaterial,
+				})
 		} else {
 			Some(if self.in_check() {
 				GameResult::Win {
@@ -464,6 +464,16 @@ impl Board {
 		}
 	}
 
+	/// Number of halfmoves since the last capture or pawn move
+	pub fn halfmove_clock(&self) -> u32 {
+		self.halfmove_clock
+	}
+
+	/// Number of the current move, starting at 1 and incremented after each move by black
+	pub fn fullmove_number(&self) -> u32 {
+		self.fullmove_number
+	}
+
 	pub fn get(&self, index: usize) -> Option<(Player, Piece)> {
 		self.repr.get(index)
 	}
@@ -473,11 +483,42 @@ impl Board {
 	}
 
 	pub fn set(&mut self, index: usize, piece: Option<(Player, Piece)>) {
-		self.repr.set(index, piece);
+		self.setp(Pos::from_value(index as u8), piece);
 	}
 
 	pub fn setp(&mut self, pos: Pos, piece: Option<(Player, Piece)>) {
+		// placing a king also removes it from its previous square
+		let old_king_pos = match piece {
+			Some((player, Piece::King)) => self.repr.king(player),
+			_ => None,
+		};
+		let toggle_keys = |board: &mut Self| {
+			board.zobrist_key ^= board.en_passant_key();
+			for pos in [Some(pos), old_king_pos.filter(|&p| p != pos)]
+				.into_iter()
+				.flatten()
+			{
+				if let Some(piece) = board.getp(pos) {
+					board.zobrist_key ^= zobrist::piece_key(piece, pos);
+				}
+			}
+		};
+		toggle_keys(self);
 		self.repr.set(pos.value() as usize, piece);
+		toggle_keys(self);
+	}
+
+	/// The player to move. Read-only, since changing it must also update the
+	/// Zobrist key; use [`BoardBuilder::side_to_move`] to set it up.
+	pub fn current_player(&self) -> Player {
+		self.current_player
+	}
+
+	/// Zobrist key of this position, covering piece placement, side to move,
+	/// castling rights and the en passant file (if a capture is possible). The keys
+	/// are not compatible with Polyglot opening books.
+	pub fn zobrist_key(&self) -> u64 {
+		self.zobrist_key
 	}
 
 	pub fn material_difference(&self) -> i32 {
@@ -497,6 +538,12 @@ impl Board {
 	}
 }
 
+impl hash::Hash for Board {
+	fn hash<H: hash::Hasher>(&self, state: &mut H) {
+		self.zobrist_key.hash(state);
+	}
+}
+
 impl fmt::Display for Board {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		writeln!(f, "+---+---+---+---+---+---+---+---+")?;
@@ -0,0 +1,152 @@
+use crate::*;
+
+impl Board {
+	/// Returns the pieces of the given player that attack the given square. Only
+	/// direct attacks count: a slider behind another piece (an X-ray) does not.
+	/// Pawns only attack diagonally, so en passant captures are not included.
+	pub fn attackers_to(&self, pos: Pos, player: Player) -> Bitboard {
+		self.attackers_with(pos, player, self.repr.occupied())
+	}
+
+	/// Returns true if any piece of the given player attacks the given square.
+	pub fn is_attacked(&self, pos: Pos, by: Player) -> bool {
+		!self.attackers_to(pos, by).is_zero()
+	}
+
+	/// Returns the pieces giving check to the current player's king.
+	pub fn checkers(&self) -> Bitboard {
+		let player = self.current_player;
+		self.attackers_to(self.repr.king_pos(player), !player)
+	}
+
+	/// Returns the pieces of the given player that are pinned to their own king,
+	/// i.e. the only piece between the king and an enemy slider.
+	pub fn pinned(&self, player: Player) -> Bitboard {
+		self.pins(player, self.repr.occupied()).pinned
+	}
+
+	/// Returns every square attacked by the given player's pieces, including
+	/// squares occupied by their own pieces.
+	pub fn attacked_squares(&self, player: Player) -> Bitboard {
+		let r = &self.repr;
+		let occupancy = r.occupied();
+		let (pawn_attacks, knights) = match player {
+			Player::White => (r.white_pawns.white_pawn_attack_shifts(), r.white_knights),
+			Player::Black => (r.black_pawns.black_pawn_attack_shifts(), r.black_knights),
+		};
+		let king_attacks = r.king(player).map_or(Bitboard::empty(), Pos::adjacent);
+		let mut attacked = pawn_attacks | knights.knight_shifts() | king_attacks;
+		for pos in r.player_pieces_checks_1(player) {
+			attacked = attacked | pos.bishop_attacks(occupancy);
+		}
+		for pos in r.player_pieces_checks_2(player) {
+			attacked = attacked | pos.rook_attacks(occupancy);
+		}
+		attacked
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	fn squares(squares: &[&str]) -> Bitboard {
+		let mut bitboard = Bitboard::empty();
+		for &square in squares {
+			bitboard.set(Pos::try_from(square).unwrap());
+		}
+		bitboard
+	}
+
+	fn pos(square: &str) -> Pos {
+		Pos::try_from(square).unwrap()
+	}
+
+	#[test]
+	fn attackers() {
+		let board = Board::from_fen("k3r3/4r3/8/2n5/8/3P4/4R3/4K3 w - - 0 1");
+		// the rook on e8 is blocked by the rook on e7; X-rays are not counted
+		assert_eq!(
+			board.attackers_to(pos("e5"), Player::Black),
+			squares(&["e7"])
+		);
+		assert_eq!(
+			board.attackers_to(pos("e4"), Player::Black),
+			squares(&["e7", "c5"])
+		);
+		assert_eq!(
+			board.attackers_to(pos("e4"), Player::White),
+			squares(&["d3", "e2"])
+		);
+		assert!(board.is_attacked(pos("e3"), Player::Black));
+		assert!(!board.is_attacked(pos("e1"), Player::Black));
+		assert!(!board.is_attacked(pos("c3"), Player::Black));
+		assert!(board.checkers().is_zero());
+
+		// a double check, and squares behind the king along the checking line
+		let board = Board::from_fen("4k3/8/8/8/1b6/8/8/r3K3 w - - 0 1");
+		assert_eq!(board.checkers(), squares(&["a1", "b4"]));
+		assert!(board.in_check());
+		assert!(!board.is_attacked(pos("f1"), Player::Black));
+		assert!(board.legal_moves().iter().all(|m| m.to() != pos("f1")));
+	}
+
+	#[test]
+	fn pins() {
+		// the rook on e2 is pinned by the rook on e7, which is backed up by the one on e8
+		let board = Board::from_fen("k3r3/4r3/8/8/8/8/4R3/4K3 w - - 0 1");
+		assert_eq!(board.pinned(Player::White), squares(&["e2"]));
+		assert!(board.pinned(Player::Black).is_zero());
+
+		// two pieces between the king and the slider: nothing is pinned
+		let board = Board::from_fen("k3r3/8/8/8/4n3/8/4R3/4K3 w - - 0 1");
+		assert!(board.pinned(Player::White).is_zero());
+		assert!(board.pinned(Player::Black).is_zero());
+
+		// pins on diagonals and of the player not to move
+		let board = Board::from_fen("4k3/3n4/8/1B6/6b1/8/8/4K2R w - - 0 1");
+		assert_eq!(board.pinned(Player::Black), squares(&["d7"]));
+		assert!(board.pinned(Player::White).is_zero());
+		let board = Board::from_fen("4k3/8/8/8/8/8/5N2/4K1b1 w - - 0 1");
+		assert!(board.pinned(Player::White).is_zero());
+		let board = Board::from_fen("4k3/8/8/8/8/6b1/5N2/4K3 w - - 0 1");
+		assert_eq!(board.pinned(Player::White), squares(&["f2"]));
+	}
+
+	#[test]
+	fn en_passant() {
+		// neither pawn is pinned on its own, but capturing en passant removes both
+		let board = Board::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1");
+		assert!(board.pinned(Player::White).is_zero());
+		assert!(!board.legal_moves().contains(&Move::from_uci("b5c6")));
+		// the pawn giving check can be captured en passant
+		let board = Board::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+		assert_eq!(board.checkers(), squares(&["d4"]));
+		assert!(board.legal_moves().contains(&Move::from_uci("e4d3")));
+		// en passant does not count as attacking the pawn
+		assert_eq!(
+			board.attackers_to(pos("d4"), Player::Black),
+			squares(&["c5"])
+		);
+		assert!(board.is_attacked(pos("d3"), Player::Black));
+	}
+
+	#[test]
+	fn attacked_squares() {
+		let board = Board::initial_position();
+		let attacked = board.attacked_squares(Player::White);
+		assert_eq!(attacked.count(), 22);
+		assert!(!attacked.get(pos("a1")) && !attacked.get(pos("h1")));
+		assert!(attacked.get(pos("h3")) && !attacked.get(pos("e4")));
+		assert_eq!(
+			board.attacked_squares(Player::Black).count(),
+			attacked.count()
+		);
+
+		let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+		let attacked = board.attacked_squares(Player::White);
+		assert!(attacked.get(pos("a8")) && attacked.get(pos("e1")));
+		assert!(!attacked.get(pos("g1")));
+		assert_eq!(attacked.count(), 15);
+	}
+}
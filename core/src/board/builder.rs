@@ -0,0 +1,290 @@
+use crate::*;
+
+/// Builds a [`Board`] piece by piece, e.g. for tests or a position editor.
+/// The position is only checked for legality by [`BoardBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+	board: Board,
+}
+
+impl BoardBuilder {
+	/// Constructs a builder for an empty board with white to move
+	pub fn new() -> Self {
+		Self {
+			board: Board::empty(),
+		}
+	}
+
+	/// Parses the ASCII diagram printed by `impl Display for Board`. The border is
+	/// optional: each rank may also be written as 8 characters such as `rnbqkbnr`
+	/// or `. . . . k . . .`, from the 8th rank down to the 1st. Each player may have
+	/// at most one king.
+	pub fn from_diagram(diagram: &str) -> Result<Self, &'static str> {
+		let mut builder = Self::new();
+		let mut ranks = RANKS.iter().copied().rev();
+		for line in diagram.lines().map(str::trim) {
+			if line.is_empty() || line.starts_with('+') {
+				continue;
+			}
+			let rank = ranks.next().ok_or("diagram contains more than 8 ranks")?;
+			let mut squares = line.chars().filter(|&ch| ch != '|' && !ch.is_whitespace());
+			for file in FILES {
+				let piece = match squares.next().ok_or("rank contains fewer than 8 squares")? {
+					'.' => None,
+					ch => Some(Piece::try_from_ascii_char(ch).ok_or("invalid piece")?),
+				};
+				if let Some((player, Piece::King)) = piece
+					&& builder.board.repr.king(player).is_some()
+				{
+					return Err("more than one king of the same colour");
+				}
+				builder.board.setp(Pos::new(file, rank), piece);
+			}
+			if squares.next().is_some() {
+				return Err("rank contains more than 8 squares");
+			}
+		}
+		if ranks.next().is_some() {
+			return Err("diagram contains fewer than 8 ranks");
+		}
+		Ok(builder)
+	}
+
+	/// Places a piece, replacing whatever was on the square. Placing a king moves
+	/// that player's king if they already have one.
+	pub fn piece(mut self, pos: Pos, player: Player, piece: Piece) -> Self {
+		self.board.setp(pos, Some((player, piece)));
+		self
+	}
+
+	/// Removes the piece on the given square, if any
+	pub fn remove(mut self, pos: Pos) -> Self {
+		self.board.setp(pos, None);
+		self
+	}
+
+	pub fn side_to_move(mut self, player: Player) -> Self {
+		self.board.current_player = player;
+		self
+	}
+
+	/// Sets or removes a castling right, given as the file of the rook to castle with
+	pub fn castling(mut self, player: Player, side: CastlingSide, rook_file: Option<File>) -> Self {
+		let right = match (player, side) {
+			(Player::White, CastlingSide::Kingside) => &mut self.board.white_kingside_castle,
+			(Player::White, CastlingSide::Queenside) => &mut self.board.white_queenside_castle,
+			(Player::Black, CastlingSide::Kingside) => &mut self.board.black_kingside_castle,
+			(Player::Black, CastlingSide::Queenside) => &mut self.board.black_queenside_castle,
+		};
+		*right = rook_file;
+		self
+	}
+
+	pub fn en_passant(mut self, target: Option<Pos>) -> Self {
+		self.board.en_passant_target = target;
+		self
+	}
+
+	/// Removes all pieces, castling rights and the en passant target
+	pub fn clear(self) -> Self {
+		Self {
+			board: Board {
+				current_player: self.board.current_player,
+				..Board::empty()
+			},
+		}
+	}
+
+	/// Flips the board vertically and swaps the colours of all pieces, the side to
+	/// move and the castling rights, giving the same position from the other side.
+	pub fn mirror(self) -> Self {
+		let old = self.board;
+		let flip = |pos: Pos| Pos::new(pos.file(), Rank::from_value(7 - pos.rank().value()));
+		let mut board = Board::empty();
+		for i in 0..64 {
+			let pos = Pos::from_value(i);
+			let piece = old.getp(pos).map(|(player, piece)| (!player, piece));
+			board.setp(flip(pos), piece);
+		}
+		board.current_player = !old.current_player;
+		board.en_passant_target = old.en_passant_target.map(flip);
+		board.white_kingside_castle = old.black_kingside_castle;
+		board.white_queenside_castle = old.black_queenside_castle;
+		board.black_kingside_castle = old.white_kingside_castle;
+		board.black_queenside_castle = old.white_queenside_castle;
+		board.halfmove_clock = old.halfmove_clock;
+		board.fullmove_number = old.fullmove_number;
+		Self { board }
+	}
+
+	/// Returns the board, or the first reason it is not a legal position
+	pub fn build(self) -> Result<Board, PositionError> {
+		let mut board = self.board;
+		board.zobrist_key = board.compute_zobrist_key();
+		board.validate().map_err(|errors| errors[0])?;
+		Ok(board)
+	}
+}
+
+impl Default for BoardBuilder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl From<Board> for BoardBuilder {
+	fn from(board: Board) -> Self {
+		Self { board }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pos(s: &str) -> Pos {
+		Pos::try_from(s).unwrap()
+	}
+
+	#[test]
+	fn build() {
+		let mut builder = BoardBuilder::new();
+		for (i, piece) in HOME_ROW.iter().copied().enumerate() {
+			let file = File::from_value(i as u8);
+			builder = builder
+				.piece(Pos::new(file, Rank::One), Player::White, piece)
+				.piece(Pos::new(file, Rank::Two), Player::White, Piece::Pawn)
+				.piece(Pos::new(file, Rank::Seven), Player::Black, Piece::Pawn)
+				.piece(Pos::new(file, Rank::Eight), Player::Black, piece);
+		}
+		for player in [Player::White, Player::Black] {
+			builder = builder
+				.castling(player, CastlingSide::Kingside, Some(File::H))
+				.castling(player, CastlingSide::Queenside, Some(File::A));
+		}
+		assert_eq!(builder.clone().build(), Ok(Board::initial_position()));
+
+		let board = builder
+			.clone()
+			.remove(pos("e2"))
+			.piece(pos("e4"), Player::White, Piece::Pawn)
+			.side_to_move(Player::Black)
+			.en_passant(Some(pos("e3")))
+			.build()
+			.unwrap();
+		assert_eq!(
+			board,
+			Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+		);
+
+		assert_eq!(
+			builder.clear().build(),
+			Err(PositionError::MissingKing(Player::White))
+		);
+		assert_eq!(
+			BoardBuilder::new()
+				.piece(pos("e1"), Player::White, Piece::King)
+				.piece(pos("e8"), Player::Black, Piece::King)
+				.piece(pos("d8"), Player::Black, Piece::King)
+				.build()
+				.map(|board| board.to_fen()),
+			Ok("3k4/8/8/8/8/8/8/4K3 w - - 0 1".to_string())
+		);
+		// moving a king in an editor places it on the new square
+		let moved = BoardBuilder::from(Board::initial_position())
+			.remove(pos("e2"))
+			.piece(pos("e4"), Player::White, Piece::King)
+			.castling(Player::White, CastlingSide::Kingside, None)
+			.castling(Player::White, CastlingSide::Queenside, None)
+			.build()
+			.unwrap();
+		assert_eq!(
+			moved.to_fen(),
+			"rnbqkbnr/pppppppp/8/8/4K3/8/PPPP1PPP/RNBQ1BNR w kq - 0 1"
+		);
+		assert_eq!(
+			BoardBuilder::new()
+				.piece(pos("e1"), Player::White, Piece::King)
+				.piece(pos("e8"), Player::Black, Piece::King)
+				.castling(Player::White, CastlingSide::Kingside, Some(File::H))
+				.build(),
+			Err(PositionError::InvalidCastlingRight(
+				Player::White,
+				CastlingSide::Kingside
+			))
+		);
+	}
+
+	#[test]
+	fn mirror() {
+		let board = Board::from_fen("r3k2r/pp3ppp/8/3pP3/8/8/PPP2PPP/R3K2R w Kq d6 3 12");
+		let mirrored = BoardBuilder::from(board).mirror().build().unwrap();
+		assert_eq!(
+			mirrored.to_fen(),
+			"r3k2r/ppp2ppp/8/8/3Pp3/8/PP3PPP/R3K2R b Qk d3 3 12"
+		);
+		assert_eq!(BoardBuilder::from(mirrored).mirror().build(), Ok(board));
+		let initial = Board::initial_position();
+		assert_eq!(
+			BoardBuilder::from(initial)
+				.mirror()
+				.side_to_move(Player::White)
+				.build(),
+			Ok(initial)
+		);
+	}
+
+	#[test]
+	fn diagrams() {
+		let board =
+			Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+		let parsed = BoardBuilder::from_diagram(&board.to_string())
+			.unwrap()
+			.build()
+			.unwrap();
+		assert_eq!(
+			parsed.to_fen(),
+			"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w - - 0 1"
+		);
+
+		let compact = BoardBuilder::from_diagram(
+			"
+			. . . . k . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . P . . .
+			. . . . K . . R
+			",
+		)
+		.unwrap()
+		.castling(Player::White, CastlingSide::Kingside, Some(File::H))
+		.build()
+		.unwrap();
+		assert_eq!(compact.to_fen(), "4k3/8/8/8/8/8/4P3/4K2R w K - 0 1");
+
+		assert_eq!(
+			BoardBuilder::from_diagram("rnbqkbnr").unwrap_err(),
+			"diagram contains fewer than 8 ranks"
+		);
+		assert_eq!(
+			BoardBuilder::from_diagram(&"........\n".repeat(9)).unwrap_err(),
+			"diagram contains more than 8 ranks"
+		);
+		assert_eq!(
+			BoardBuilder::from_diagram(&format!("KK......\n{}", "........\n".repeat(7)))
+				.unwrap_err(),
+			"more than one king of the same colour"
+		);
+		assert_eq!(
+			BoardBuilder::from_diagram(&"......x.\n".repeat(8)).unwrap_err(),
+			"invalid piece"
+		);
+		assert_eq!(
+			BoardBuilder::from_diagram(&".......\n".repeat(8)).unwrap_err(),
+			"rank contains fewer than 8 squares"
+		);
+	}
+}
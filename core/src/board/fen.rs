@@ -0,0 +1,387 @@
+use crate::*;
+use core::{fmt, str};
+
+/// The field of a FEN string that failed to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenField {
+	Placement,
+	SideToMove,
+	Castling,
+	EnPassant,
+	HalfmoveClock,
+	FullmoveNumber,
+}
+
+impl fmt::Display for FenField {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let s = match self {
+			FenField::Placement => "piece placement",
+			FenField::SideToMove => "side to move",
+			FenField::Castling => "castling rights",
+			FenField::EnPassant => "en passant target",
+			FenField::HalfmoveClock => "halfmove clock",
+			FenField::FullmoveNumber => "fullmove number",
+		};
+		write!(f, "{s}")
+	}
+}
+
+/// Error returned when a FEN string cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FenError {
+	/// The field that was invalid
+	pub field: FenField,
+	/// Byte offset into the FEN string at which the error was detected
+	pub offset: usize,
+	pub message: &'static str,
+}
+
+impl fmt::Display for FenError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"invalid fen: {} in {} at offset {}",
+			self.message, self.field, self.offset
+		)
+	}
+}
+
+impl std::error::Error for FenError {}
+
+/// Splits a FEN string into its space-separated fields, along with their byte offsets.
+fn fields(fen: &str) -> impl Iterator<Item = (usize, &str)> {
+	fen.split(' ')
+		.scan(0, |offset, field| {
+			let start = *offset;
+			*offset += field.len() + 1;
+			Some((start, field))
+		})
+		.filter(|(_, field)| !field.is_empty())
+}
+
+impl Board {
+	/// Parses a position in Forsyth-Edwards Notation, rejecting positions that
+	/// fail [`Board::validate`].
+	pub fn try_from_fen(fen: &str) -> Result<Self, FenError> {
+		let board = Self::try_from_fen_unvalidated(fen)?;
+		if let Err(errors) = board.validate() {
+			let field = errors[0].fen_field();
+			return Err(FenError {
+				field,
+				offset: fields(fen)
+					.nth(field as usize)
+					.map_or(0, |(offset, _)| offset),
+				message: errors[0].message(),
+			});
+		}
+		Ok(board)
+	}
+
+	/// Parses a position in Forsyth-Edwards Notation without checking that it is
+	/// legal, e.g. for a position editor. Move generation on such a board may panic.
+	/// A second king of the same colour is still an error, since the board can only
+	/// hold one.
+	pub fn try_from_fen_unvalidated(fen: &str) -> Result<Self, FenError> {
+		let mut result = Self::empty();
+		let mut fields = fields(fen);
+		let mut next_field = |field| {
+			fields.next().ok_or(FenError {
+				field,
+				offset: fen.len(),
+				message: "missing field",
+			})
+		};
+
+		let (offset, placement) = next_field(FenField::Placement)?;
+		result.parse_placement(offset, placement)?;
+
+		let (offset, side_to_move) = next_field(FenField::SideToMove)?;
+		result.current_player = match side_to_move {
+			"w" => Player::White,
+			"b" => Player::Black,
+			_ => {
+				return Err(FenError {
+					field: FenField::SideToMove,
+					offset,
+					message: "expected 'w' or 'b'",
+				});
+			}
+		};
+
+		let (offset, castling) = next_field(FenField::Castling)?;
+		result.parse_castling(offset, castling)?;
+
+		let (offset, en_passant) = next_field(FenField::EnPassant)?;
+		result.en_passant_target = result.parse_en_passant(offset, en_passant)?;
+
+		// the move counters are optional, e.g. in EPD strings
+		if let Some((offset, halfmove_clock)) = fields.next() {
+			result.halfmove_clock = parse_counter(halfmove_clock).ok_or(FenError {
+				field: FenField::HalfmoveClock,
+				offset,
+				message: "expected a non-negative integer",
+			})?;
+		}
+		if let Some((offset, fullmove_number)) = fields.next() {
+			result.fullmove_number = match parse_counter(fullmove_number) {
+				Some(0) | None => {
+					return Err(FenError {
+						field: FenField::FullmoveNumber,
+						offset,
+						message: "expected a positive integer",
+					});
+				}
+				Some(n) => n,
+			};
+		}
+		if let Some((offset, _)) = fields.next() {
+			return Err(FenError {
+				field: FenField::FullmoveNumber,
+				offset,
+				message: "unexpected field after the fullmove number",
+			});
+		}
+		result.zobrist_key = result.compute_zobrist_key();
+		Ok(result)
+	}
+
+	fn parse_placement(&mut self, offset: usize, placement: &str) -> Result<(), FenError> {
+		let error = |offset, message| FenError {
+			field: FenField::Placement,
+			offset,
+			message,
+		};
+		let mut rank = Rank::Eight;
+		let mut file = 0;
+		// placing a second king would move the first, so it has to be caught here
+		let mut kings = [false; 2];
+		for (i, ch) in placement.char_indices() {
+			match ch {
+				'/' => {
+					if file != 8 {
+						return Err(error(offset + i, "rank does not contain 8 squares"));
+					}
+					rank = rank.prev().ok_or(error(offset + i, "too many ranks"))?;
+					file = 0;
+				}
+				'1'..='8' => {
+					file += ch as u8 - b'0';
+					if file > 8 {
+						return Err(error(offset + i, "rank contains more than 8 squares"));
+					}
+				}
+				_ => {
+					let piece =
+						Piece::try_from_ascii_char(ch).ok_or(error(offset + i, "invalid piece"))?;
+					if file >= 8 {
+						return Err(error(offset + i, "rank contains more than 8 squares"));
+					}
+					if let (player, Piece::King) = piece {
+						if kings[player as usize] {
+							return Err(error(offset + i, "more than one king of the same colour"));
+						}
+						kings[player as usize] = true;
+					}
+					self.setp(Pos::new(File::from_value(file), rank), Some(piece));
+					file += 1;
+				}
+			}
+		}
+		if rank != Rank::One || file != 8 {
+			return Err(error(
+				offset + placement.len(),
+				"expected 8 ranks of 8 squares",
+			));
+		}
+		Ok(())
+	}
+
+	/// Parses castling rights, either as `KQkq` (with X-FEN's convention that these
+	/// refer to the outermost rook on each side) or as Shredder-FEN rook files.
+	fn parse_castling(&mut self, offset: usize, castling: &str) -> Result<(), FenError> {
+		if castling == "-" {
+			return Ok(());
+		}
+		for (i, ch) in castling.char_indices() {
+			let player = if ch.is_ascii_uppercase() {
+				Player::White
+			} else {
+				Player::Black
+			};
+			let (kingside, file) = match ch.to_ascii_uppercase() {
+				'K' => (true, self.outermost_rook(player, true).unwrap_or(File::H)),
+				'Q' => (false, self.outermost_rook(player, false).unwrap_or(File::A)),
+				'A'..='H' => {
+					let file = File::try_from(ch.to_ascii_lowercase()).expect("invalid file");
+					(file > self.castling_king(player).file(), file)
+				}
+				_ => {
+					return Err(FenError {
+						field: FenField::Castling,
+						offset: offset + i,
+						message: "expected '-', a combination of 'KQkq' or rook files",
+					});
+				}
+			};
+			let right = match (player, kingside) {
+				(Player::White, true) => &mut self.white_kingside_castle,
+				(Player::White, false) => &mut self.white_queenside_castle,
+				(Player::Black, true) => &mut self.black_kingside_castle,
+				(Player::Black, false) => &mut self.black_queenside_castle,
+			};
+			if right.is_some() {
+				return Err(FenError {
+					field: FenField::Castling,
+					offset: offset + i,
+					message: "duplicate castling right",
+				});
+			}
+			*right = Some(file);
+		}
+		Ok(())
+	}
+
+	/// Returns the file of the given player's rook on their back rank that is
+	/// furthest from the king on the given side.
+	fn outermost_rook(&self, player: Player, kingside: bool) -> Option<File> {
+		let king = self.castling_king(player);
+		let files = FILES
+			.into_iter()
+			.filter(|&file| (file > king.file()) == kingside && file != king.file());
+		let mut rooks = files
+			.filter(|&file| self.getp(Pos::new(file, king.rank())) == Some((player, Piece::Rook)));
+		if kingside {
+			rooks.next_back()
+		} else {
+			rooks.next()
+		}
+	}
+
+	/// Returns the square of the given player's king, or their king's starting
+	/// square if they have none, so that castling rights can still be parsed.
+	fn castling_king(&self, player: Player) -> Pos {
+		let back_rank = match player {
+			Player::White => Rank::One,
+			Player::Black => Rank::Eight,
+		};
+		self.repr
+			.king(player)
+			.unwrap_or(Pos::new(File::E, back_rank))
+	}
+
+	fn parse_en_passant(&self, offset: usize, en_passant: &str) -> Result<Option<Pos>, FenError> {
+		if en_passant == "-" {
+			return Ok(None);
+		}
+		let error = |message| FenError {
+			field: FenField::EnPassant,
+			offset,
+			message,
+		};
+		let pos = Pos::try_from(en_passant).map_err(error)?;
+		let expected_rank = match self.current_player {
+			Player::White => Rank::Six,
+			Player::Black => Rank::Three,
+		};
+		if pos.rank() != expected_rank {
+			return Err(error("en passant target is on the wrong rank"));
+		}
+		Ok(Some(pos))
+	}
+
+	/// Converts this position to Forsyth-Edwards Notation. Castling rights are written
+	/// as `KQkq` where possible and as rook files otherwise, as in X-FEN.
+	pub fn to_fen(&self) -> String {
+		self.fen(false)
+	}
+
+	/// Converts this position to Shredder-FEN, which always writes castling rights
+	/// as rook files (e.g. `HAha` for the standard starting position).
+	pub fn to_shredder_fen(&self) -> String {
+		self.fen(true)
+	}
+
+	fn fen(&self, shredder: bool) -> String {
+		let mut result = String::new();
+		for rank in (0..8).rev() {
+			let mut empty = 0;
+			for file in 'a'..='h' {
+				let pos = Pos::new(File::from_value(file as u8 - b'a'), Rank::from_value(rank));
+				if let Some((player, piece)) = self.getp(pos) {
+					if empty > 0 {
+						result.push((b'0' + empty) as char);
+						empty = 0;
+					}
+					result.push(piece.ascii_char(player));
+				} else {
+					empty += 1;
+				}
+			}
+			if empty > 0 {
+				result.push((b'0' + empty) as char);
+			}
+			if rank > 0 {
+				result.push('/');
+			}
+		}
+		result.push(' ');
+		result.push(match self.current_player {
+			Player::White => 'w',
+			Player::Black => 'b',
+		});
+		result.push(' ');
+		for (player, kingside, right) in [
+			(Player::White, true, self.white_kingside_castle),
+			(Player::White, false, self.white_queenside_castle),
+			(Player::Black, true, self.black_kingside_castle),
+			(Player::Black, false, self.black_queenside_castle),
+		] {
+			let Some(file) = right else {
+				continue;
+			};
+			let default = if kingside { File::H } else { File::A };
+			let ch =
+				if !shredder && self.outermost_rook(player, kingside).unwrap_or(default) == file {
+					if kingside { 'K' } else { 'Q' }
+				} else {
+					char::from(file).to_ascii_uppercase()
+				};
+			result.push(match player {
+				Player::White => ch,
+				Player::Black => ch.to_ascii_lowercase(),
+			});
+		}
+		if result.ends_with(' ') {
+			result.push('-');
+		}
+		result.push(' ');
+		if let Some(pos) = self.en_passant_target {
+			result.push(pos.file().into());
+			result.push(pos.rank().into());
+		} else {
+			result.push('-');
+		}
+		result.push_str(&format!(
+			" {} {}",
+			self.halfmove_clock, self.fullmove_number
+		));
+		result
+	}
+}
+
+impl str::FromStr for Board {
+	type Err = FenError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::try_from_fen(s)
+	}
+}
+
+/// Parses a move counter, which must consist of ASCII digits only (unlike
+/// `u32::from_str`, which also accepts a leading '+').
+fn parse_counter(s: &str) -> Option<u32> {
+	if !s.bytes().all(|b| b.is_ascii_digit()) {
+		return None;
+	}
+	s.parse().ok()
+}
@@ -0,0 +1,351 @@
+use crate::mov::MoveFlags;
+use crate::*;
+use core::ops;
+
+/// Squares on the first rank; shift left by the rank's value for other ranks
+const FIRST_RANK: u64 = 0x0101_0101_0101_0101;
+
+/// Squares on which pawns promote, for either player
+const PROMOTION_RANKS: u64 = FIRST_RANK | (FIRST_RANK << 7);
+
+/// Which moves to generate
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+	All,
+	/// Captures (including en passant) and promotions
+	Tactical,
+	/// All other moves, including castling
+	Quiet,
+}
+
+/// Returns the squares strictly between two squares on the same rank, file or
+/// diagonal, or an empty bitboard if they are not aligned.
+fn between(a: Pos, b: Pos) -> Bitboard {
+	let (a_bit, b_bit) = (Bitboard::single_bit(a), Bitboard::single_bit(b));
+	if a.rook_moves().get(b) {
+		a.rook_attacks(b_bit) & b.rook_attacks(a_bit)
+	} else if a.bishop_moves().get(b) {
+		a.bishop_attacks(b_bit) & b.bishop_attacks(a_bit)
+	} else {
+		Bitboard::empty()
+	}
+}
+
+/// Pieces pinned to their king, along with the squares each may still move to
+pub(super) struct Pins {
+	pub(super) pinned: Bitboard,
+	rays: [(Pos, Bitboard); 8],
+	count: usize,
+}
+
+impl Pins {
+	fn ray(&self, pos: Pos) -> Bitboard {
+		if !self.pinned.get(pos) {
+			return Bitboard::full();
+		}
+		self.rays[..self.count]
+			.iter()
+			.find(|(pinned, _)| *pinned == pos)
+			.expect("pinned piece has no pin ray")
+			.1
+	}
+}
+
+impl Board {
+	/// Returns the pieces of the given player that attack the given square, with
+	/// sliding attacks computed for the given occupancy.
+	pub(super) fn attackers_with(&self, pos: Pos, player: Player, occupancy: Bitboard) -> Bitboard {
+		let r = &self.repr;
+		let bit = Bitboard::single_bit(pos);
+		let (pawns, knights) = match player {
+			Player::White => (
+				bit.black_pawn_attack_shifts() & r.white_pawns,
+				r.white_knights,
+			),
+			Player::Black => (
+				bit.white_pawn_attack_shifts() & r.black_pawns,
+				r.black_knights,
+			),
+		};
+		let king = r.pieces(player, Piece::King) & pos.adjacent();
+		pawns
+			| king | (pos.knight_moves() & knights)
+			| (pos.bishop_attacks(occupancy) & r.player_pieces_checks_1(player))
+			| (pos.rook_attacks(occupancy) & r.player_pieces_checks_2(player))
+	}
+
+	/// Finds the given player's pieces that are pinned to their king.
+	pub(super) fn pins(&self, player: Player, occupancy: Bitboard) -> Pins {
+		let king = self.repr.king_pos(player);
+		let enemies = self.repr.player_pieces(!player);
+		// sliders that would attack the king if our own pieces were not in the way
+		let snipers = (king.bishop_attacks(enemies) & self.repr.player_pieces_checks_1(!player))
+			| (king.rook_attacks(enemies) & self.repr.player_pieces_checks_2(!player));
+		let mut pins = Pins {
+			pinned: Bitboard::empty(),
+			rays: [(king, Bitboard::empty()); 8],
+			count: 0,
+		};
+		for sniper in snipers {
+			let ray = between(king, sniper);
+			let blockers = ray & occupancy;
+			if blockers.count() == 1 && !(blockers & self.repr.player_pieces(player)).is_zero() {
+				let pinned = Pos::from_value(blockers.ilog2());
+				pins.pinned.set(pinned);
+				pins.rays[pins.count] = (pinned, ray | Bitboard::single_bit(sniper));
+				pins.count += 1;
+			}
+		}
+		pins
+	}
+
+	/// Returns the squares the given piece could move to if there were no checks or
+	/// pins, excluding en passant and castling.
+	pub(super) fn pseudo_legal_targets(
+		&self,
+		pos: Pos,
+		(player, piece): (Player, Piece),
+	) -> Bitboard {
+		let occupancy = self.repr.occupied();
+		let own = self.repr.player_pieces(player);
+		match piece {
+			Piece::Pawn => {
+				let bit = Bitboard::single_bit(pos);
+				let empty = !occupancy;
+				let (single, double, attacks) = match player {
+					Player::White => {
+						let single = bit.shift_up() & empty;
+						let double_rank = Bitboard::new(FIRST_RANK << Rank::Three.value());
+						(
+							single,
+							(single & double_rank).shift_up() & empty,
+							bit.white_pawn_attack_shifts(),
+						)
+					}
+					Player::Black => {
+						let single = bit.shift_down() & empty;
+						let double_rank = Bitboard::new(FIRST_RANK << Rank::Six.value());
+						(
+							single,
+							(single & double_rank).shift_down() & empty,
+							bit.black_pawn_attack_shifts(),
+						)
+					}
+				};
+				single | double | (attacks & self.repr.player_pieces(!player))
+			}
+			Piece::Knight => !own & pos.knight_moves(),
+			Piece::Bishop => !own & pos.bishop_attacks(occupancy),
+			Piece::Rook => !own & pos.rook_attacks(occupancy),
+			Piece::Queen => !own & pos.queen_attacks(occupancy),
+			Piece::King => !own & pos.adjacent(),
+		}
+	}
+
+	/// Checks whether capturing en passant from the given square would leave the
+	/// king in check, taking into account that two pawns leave the same rank.
+	fn en_passant_is_legal(&self, from: Pos, target: Pos, king: Pos, check_mask: Bitboard) -> bool {
+		let captured = Pos::new(target.file(), from.rank());
+		let captured_bit = Bitboard::single_bit(captured);
+		if ((Bitboard::single_bit(target) | captured_bit) & check_mask).is_zero() {
+			return false;
+		}
+		let occupancy = (self.repr.occupied() & !Bitboard::single_bit(from) & !captured_bit)
+			| Bitboard::single_bit(target);
+		let player = self.current_player;
+		(king.bishop_attacks(occupancy) & self.repr.player_pieces_checks_1(!player)).is_zero()
+			&& (king.rook_attacks(occupancy) & self.repr.player_pieces_checks_2(!player)).is_zero()
+	}
+
+	/// Calls `add_move` for every legal move in this position, stopping early if it
+	/// returns [`ops::ControlFlow::Break`].
+	///
+	/// Checkers and pinned pieces are computed once, so that only legal moves are
+	/// generated, without trying each move on a copy of the board.
+	pub fn all_moves(&self, add_move: impl FnMut(Move) -> ops::ControlFlow<()>) {
+		self.generate_moves(Stage::All, add_move);
+	}
+
+	/// Returns all legal captures, en passant captures and promotions.
+	pub fn capture_moves(&self) -> MoveList {
+		self.stage_moves(Stage::Tactical)
+	}
+
+	/// Returns all legal moves that neither capture nor promote, including castling.
+	pub fn quiet_moves(&self) -> MoveList {
+		self.stage_moves(Stage::Quiet)
+	}
+
+	fn stage_moves(&self, stage: Stage) -> MoveList {
+		let mut moves = MoveList::new();
+		self.generate_moves(stage, |mov| {
+			moves.push(mov);
+			ops::ControlFlow::Continue(())
+		});
+		moves
+	}
+
+	fn generate_moves(&self, stage: Stage, mut add_move: impl FnMut(Move) -> ops::ControlFlow<()>) {
+		let player = self.current_player;
+		let king = self.repr.king_pos(player);
+		let occupancy = self.repr.occupied();
+		let checkers = self.attackers_with(king, !player, occupancy);
+		// squares that block or capture the checking piece
+		let check_mask = match checkers.count() {
+			0 => Bitboard::full(),
+			1 => checkers | between(king, Pos::from_value(checkers.ilog2())),
+			_ => Bitboard::empty(),
+		};
+		let pins = self.pins(player, occupancy);
+		let enemies = self.repr.player_pieces(!player);
+		let promotion_ranks = Bitboard::new(PROMOTION_RANKS);
+
+		for pos in self.repr.player_pieces(player) {
+			let piece = self.getp(pos).expect("no piece at position");
+			let mut targets = self.pseudo_legal_targets(pos, piece);
+			let tactical = if piece.1 == Piece::Pawn {
+				enemies | promotion_ranks
+			} else {
+				enemies
+			};
+			match stage {
+				Stage::All => (),
+				Stage::Tactical => targets = targets & tactical,
+				Stage::Quiet => targets = targets & !tactical,
+			}
+			if pos == king {
+				// without the king, squares behind it on a checking line count as attacked
+				let without_king = occupancy & !Bitboard::single_bit(king);
+				for target in targets {
+					if !self.attackers_with(target, !player, without_king).is_zero() {
+						targets.clear(target);
+					}
+				}
+			} else {
+				targets = targets & check_mask & pins.ray(pos);
+				if piece.1 == Piece::Pawn
+					&& stage != Stage::Quiet
+					&& let Some(target) = self.en_passant_target
+					&& self.pseudo_en_passant(pos, target)
+					&& self.en_passant_is_legal(pos, target, king, check_mask)
+				{
+					targets.set(target);
+				}
+			}
+			for target in targets {
+				// only the opponent's bitboards need checking, and kings can't be captured
+				let captured = if enemies.get(target) {
+					[
+						Piece::Pawn,
+						Piece::Knight,
+						Piece::Bishop,
+						Piece::Rook,
+						Piece::Queen,
+					]
+					.into_iter()
+					.find(|&piece| self.repr.pieces(!player, piece).get(target))
+				} else {
+					None
+				};
+				let flags = if piece.1 != Piece::Pawn {
+					MoveFlags::new(piece.1, captured)
+				} else if Some(target) == self.en_passant_target && target.file() != pos.file() {
+					MoveFlags::en_passant()
+				} else if target.rank().value().abs_diff(pos.rank().value()) == 2 {
+					MoveFlags::double_push()
+				} else {
+					MoveFlags::new(Piece::Pawn, captured)
+				};
+				if piece.1 == Piece::Pawn
+					&& (target.rank() == Rank::Eight || target.rank() == Rank::One)
+				{
+					for promotion in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+						let mov = Move::new(pos, target, Some(promotion)).with_flags(flags);
+						if add_move(mov).is_break() {
+							return;
+						}
+					}
+				} else if add_move(Move::new(pos, target, None).with_flags(flags)).is_break() {
+					return;
+				}
+			}
+		}
+		if !checkers.is_zero() || stage == Stage::Tactical {
+			return;
+		}
+		self.castling_moves(king, add_move);
+	}
+
+	/// Returns all legal moves in this position. Use [`Board::all_moves`] instead
+	/// when the search can stop after the first few moves.
+	pub fn legal_moves(&self) -> MoveList {
+		let mut moves = MoveList::new();
+		self.all_moves(|mov| {
+			moves.push(mov);
+			ops::ControlFlow::Continue(())
+		});
+		moves
+	}
+
+	/// Returns true if the pawn on the given square attacks the en passant target.
+	fn pseudo_en_passant(&self, pos: Pos, target: Pos) -> bool {
+		let bit = Bitboard::single_bit(pos);
+		match self.current_player {
+			Player::White => bit.white_pawn_attack_shifts(),
+			Player::Black => bit.black_pawn_attack_shifts(),
+		}
+		.get(target)
+	}
+
+	fn castling_moves(&self, king: Pos, mut add_move: impl FnMut(Move) -> ops::ControlFlow<()>) {
+		let player = self.current_player;
+		let (kingside_castle, queenside_castle) = self.castling_rights(player);
+		let rank = match player {
+			Player::White => Rank::One,
+			Player::Black => Rank::Eight,
+		};
+		for (rook_file, king_to, rook_to, side) in [
+			(kingside_castle, File::G, File::F, CastlingSide::Kingside),
+			(queenside_castle, File::C, File::D, CastlingSide::Queenside),
+		] {
+			let Some(rook_file) = rook_file else {
+				continue;
+			};
+			let rook = Pos::new(rook_file, rank);
+			if king.rank() != rank
+				|| self.getp(rook) != Some((player, Piece::Rook))
+				|| !self.can_castle(king, rook, Pos::new(king_to, rank), Pos::new(rook_to, rank))
+			{
+				continue;
+			}
+			// standard castling is written as a king move, Chess960 castling as the
+			// king capturing its own rook, which is never ambiguous
+			let to = if king.file() == File::E && matches!(rook_file, File::A | File::H) {
+				Pos::new(king_to, rank)
+			} else {
+				rook
+			};
+			let mov = Move::new(king, to, None).with_flags(MoveFlags::castling(side));
+			if add_move(mov).is_break() {
+				return;
+			}
+		}
+	}
+
+	/// Checks that all squares the king and rook move across are empty (apart from
+	/// the king and rook themselves) and that the king does not pass through check.
+	fn can_castle(&self, king: Pos, rook: Pos, king_to: Pos, rook_to: Pos) -> bool {
+		let span =
+			|a: Pos, b: Pos| between(a, b) | Bitboard::single_bit(a) | Bitboard::single_bit(b);
+		let occupancy = self.repr.occupied();
+		let others = occupancy & !(Bitboard::single_bit(king) | Bitboard::single_bit(rook));
+		if !((span(king, king_to) | span(rook, rook_to)) & others).is_zero() {
+			return false;
+		}
+		let without_rook = occupancy & !Bitboard::single_bit(rook);
+		span(king, king_to).into_iter().all(|pos| {
+			self.attackers_with(pos, !self.current_player, without_rook)
+				.is_zero()
+		})
+	}
+}
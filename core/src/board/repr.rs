@@ -1,4 +1,4 @@
-use crate::{Bitboard, File, Piece, Player, Pos, Rank};
+use crate::{Bitboard, Piece, Player, Pos};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Repr {
@@ -12,8 +12,8 @@ pub struct Repr {
 	pub black_rooks: Bitboard,
 	pub white_queens: Bitboard,
 	pub black_queens: Bitboard,
-	pub white_king: Pos,
-	pub black_king: Pos,
+	pub white_king: Bitboard,
+	pub black_king: Bitboard,
 }
 
 impl Repr {
@@ -29,8 +29,8 @@ impl Repr {
 			black_rooks: Bitboard::empty(),
 			white_queens: Bitboard::empty(),
 			black_queens: Bitboard::empty(),
-			white_king: Pos::new(File::E, Rank::One),
-			black_king: Pos::new(File::E, Rank::Eight),
+			white_king: Bitboard::empty(),
+			black_king: Bitboard::empty(),
 		}
 	}
 
@@ -66,10 +66,10 @@ impl Repr {
 		if self.black_queens.get(pos) {
 			return Some((Player::Black, Piece::Queen));
 		}
-		if self.white_king == pos {
+		if self.white_king.get(pos) {
 			return Some((Player::White, Piece::King));
 		}
-		if self.black_king == pos {
+		if self.black_king.get(pos) {
 			return Some((Player::Black, Piece::King));
 		}
 		None
@@ -92,6 +92,8 @@ impl Repr {
 				self.black_rooks.clear(pos);
 				self.white_queens.clear(pos);
 				self.black_queens.clear(pos);
+				self.white_king.clear(pos);
+				self.black_king.clear(pos);
 			}
 			Some((Player::White, Piece::Pawn)) => {
 				self.white_pawns.set(pos);
@@ -123,11 +125,12 @@ impl Repr {
 			Some((Player::Black, Piece::Queen)) => {
 				self.black_queens.set(pos);
 			}
+			// each player has at most one king, so placing it moves it
 			Some((Player::White, Piece::King)) => {
-				self.white_king = pos;
+				self.white_king = Bitboard::single_bit(pos);
 			}
 			Some((Player::Black, Piece::King)) => {
-				self.black_king = pos;
+				self.black_king = Bitboard::single_bit(pos);
 			}
 		}
 	}
@@ -141,7 +144,7 @@ impl Repr {
 					| self.white_bishops
 					| self.white_rooks
 					| self.white_queens
-					| Bitboard::single_bit(self.white_king)
+					| self.white_king
 			}
 			Player::Black => {
 				self.black_pawns
@@ -149,11 +152,34 @@ impl Repr {
 					| self.black_bishops
 					| self.black_rooks
 					| self.black_queens
-					| Bitboard::single_bit(self.black_king)
+					| self.black_king
 			}
 		}
 	}
 
+	/// Bitboard of the given player's pieces of the given kind
+	pub fn pieces(&self, player: Player, piece: Piece) -> Bitboard {
+		match (player, piece) {
+			(Player::White, Piece::Pawn) => self.white_pawns,
+			(Player::Black, Piece::Pawn) => self.black_pawns,
+			(Player::White, Piece::Knight) => self.white_knights,
+			(Player::Black, Piece::Knight) => self.black_knights,
+			(Player::White, Piece::Bishop) => self.white_bishops,
+			(Player::Black, Piece::Bishop) => self.black_bishops,
+			(Player::White, Piece::Rook) => self.white_rooks,
+			(Player::Black, Piece::Rook) => self.black_rooks,
+			(Player::White, Piece::Queen) => self.white_queens,
+			(Player::Black, Piece::Queen) => self.black_queens,
+			(Player::White, Piece::King) => self.white_king,
+			(Player::Black, Piece::King) => self.black_king,
+		}
+	}
+
+	/// Bitboard of all occupied squares
+	pub fn occupied(&self) -> Bitboard {
+		self.player_pieces(Player::White) | self.player_pieces(Player::Black)
+	}
+
 	pub fn player_pieces_checks_1(&self, player: Player) -> Bitboard {
 		match player {
 			Player::White => self.white_bishops | self.white_queens,
@@ -168,27 +194,17 @@ impl Repr {
 		}
 	}
 
+	/// Returns the square of the given player's king. Panics if there is none.
 	pub fn king_pos(&self, colour: Player) -> Pos {
-		match colour {
-			Player::White => self.white_king,
-			Player::Black => self.black_king,
-		}
+		self.king(colour).expect("no king on the board")
 	}
 
-	pub fn mut_bitboard(&mut self, pp: (Player, Piece)) -> &mut Bitboard {
-		match pp {
-			(Player::White, Piece::Pawn) => &mut self.white_pawns,
-			(Player::Black, Piece::Pawn) => &mut self.black_pawns,
-			(Player::White, Piece::Knight) => &mut self.white_knights,
-			(Player::Black, Piece::Knight) => &mut self.black_knights,
-			(Player::White, Piece::Bishop) => &mut self.white_bishops,
-			(Player::Black, Piece::Bishop) => &mut self.black_bishops,
-			(Player::White, Piece::Rook) => &mut self.white_rooks,
-			(Player::Black, Piece::Rook) => &mut self.black_rooks,
-			(Player::White, Piece::Queen) => &mut self.white_queens,
-			(Player::Black, Piece::Queen) => &mut self.black_queens,
-			(Player::White, Piece::King) => panic!("Cannot mutate king bitboard"),
-			(Player::Black, Piece::King) => panic!("Cannot mutate king bitboard"),
-		}
+	/// Returns the square of the given player's king, if they have one
+	pub fn king(&self, colour: Player) -> Option<Pos> {
+		let king = match colour {
+			Player::White => self.white_king,
+			Player::Black => self.black_king,
+		};
+		(!king.is_zero()).then(|| Pos::from_value(king.ilog2()))
 	}
 }
@@ -0,0 +1,161 @@
+use crate::*;
+
+const PIECES: [Piece; 6] = [
+	Piece::Pawn,
+	Piece::Knight,
+	Piece::Bishop,
+	Piece::Rook,
+	Piece::Queen,
+	Piece::King,
+];
+
+impl Board {
+	/// Static exchange evaluation: returns the material the current player wins
+	/// (in centipawns) if both players keep recapturing on the target square of
+	/// the given move with their least valuable piece, and each may stop when
+	/// recapturing would lose material. Attackers behind sliders join in as the
+	/// pieces in front of them capture, and pawns reaching the last rank promote
+	/// to queens. Pins are ignored.
+	pub fn see(&self, mov: Move) -> i32 {
+		let mov = self.annotate(mov);
+		if mov.castling_side().is_some() {
+			return 0;
+		}
+		let (player, piece) = self.getp(mov.from()).expect("no piece at from");
+		let mut occupancy = self.repr.occupied() & !Bitboard::single_bit(mov.from());
+		if mov.is_en_passant() {
+			let pawn = Pos::new(mov.to().file(), mov.from().rank());
+			occupancy = occupancy & !Bitboard::single_bit(pawn);
+		}
+		let captured = mov.captured_piece().map_or(0, Piece::value);
+		let promotion_bonus = |piece: Piece| piece.value() - Piece::Pawn.value();
+
+		// gains[i] is the material balance, for the player making the i-th capture,
+		// if the exchange stops right after it
+		let mut gains = [0; 32];
+		gains[0] = captured;
+		let mut on_square = piece;
+		if let Some(promotion) = mov.promotion() {
+			gains[0] += promotion_bonus(promotion);
+			on_square = promotion;
+		}
+		let promotes = matches!(mov.to().rank(), Rank::One | Rank::Eight);
+
+		let mut side = !player;
+		let mut depth = 0;
+		loop {
+			let attackers = self.attackers_with(mov.to(), side, occupancy) & occupancy;
+			let Some((attacker, from)) = PIECES.into_iter().find_map(|piece| {
+				let pieces = attackers & self.repr.pieces(side, piece);
+				(!pieces.is_zero()).then(|| (piece, pieces.ilog2()))
+			}) else {
+				break;
+			};
+			// the king may only recapture if the square is no longer defended
+			if attacker == Piece::King
+				&& !(self.attackers_with(mov.to(), !side, occupancy) & occupancy).is_zero()
+			{
+				break;
+			}
+			depth += 1;
+			gains[depth] = on_square.value() - gains[depth - 1];
+			on_square = attacker;
+			if attacker == Piece::Pawn && promotes {
+				gains[depth] += promotion_bonus(Piece::Queen);
+				on_square = Piece::Queen;
+			}
+			occupancy = occupancy & !Bitboard::single_bit(Pos::from_value(from));
+			side = !side;
+		}
+		// each player only continues the exchange if it does not lose them material
+		while depth > 0 {
+			gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+			depth -= 1;
+		}
+		gains[0]
+	}
+
+	/// Returns true if the static exchange evaluation of the given move is at
+	/// least the given threshold.
+	pub fn see_ge(&self, mov: Move, threshold: i32) -> bool {
+		self.see(mov) >= threshold
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[track_caller]
+	fn see(fen: &str, san: &str) -> i32 {
+		let board = Board::from_fen(fen);
+		board.see(Move::from_san(&board, san).unwrap())
+	}
+
+	#[test]
+	fn exchanges() {
+		// undefended pawn
+		assert_eq!(
+			see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "Rxe5"),
+			100
+		);
+		// pawn defended by a pawn: knight for pawn
+		assert_eq!(
+			see("4k3/8/3p4/4p3/8/5N2/8/4K3 w - - 0 1", "Nxe5"),
+			100 - 300
+		);
+		// the queen behind the rook does not help, the bishop behind the knight does
+		assert_eq!(
+			see(
+				"1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+				"Nxe5"
+			),
+			100 - 300
+		);
+		// an equal trade
+		assert_eq!(see("4k3/8/3n4/8/4N3/8/8/4K3 w - - 0 1", "Nxd6"), 300);
+		assert_eq!(see("4k3/2p5/3n4/8/4N3/8/8/4K3 w - - 0 1", "Nxd6"), 0);
+		// quiet moves to attacked squares lose the piece
+		assert_eq!(see("4k3/8/3p4/8/8/8/8/2R1K3 w - - 0 1", "Rc5"), -500);
+		assert_eq!(see("4k3/8/3p4/8/8/8/8/2R1K3 w - - 0 1", "Rc4"), 0);
+	}
+
+	#[test]
+	fn x_rays() {
+		// doubled rooks win the pawn despite the defending rook
+		assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "Rxd5"), 100);
+		// but not against a second defender behind the first
+		assert_eq!(
+			see("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "Rxd5"),
+			100 - 500
+		);
+		// a queen behind a bishop
+		assert_eq!(see("4k3/6p1/5n2/8/8/2B5/8/4K3 w - - 0 1", "Bxf6"), 0);
+		assert_eq!(see("4k3/6p1/5n2/8/8/2B5/1Q6/4K3 w - - 0 1", "Bxf6"), 100);
+		// the king cannot recapture on a defended square
+		assert_eq!(see("3k4/3p4/8/8/8/8/8/3RK3 w - - 0 1", "Rxd7"), 100 - 500);
+		assert_eq!(see("3k4/3p4/8/8/8/8/3R4/3RK3 w - - 0 1", "Rxd7"), 100);
+		assert_eq!(see("8/8/8/8/8/4k3/3r4/3QK3 w - - 0 1", "Qxd2"), 500);
+		assert_eq!(see("8/8/8/8/8/4k3/3r4/3Q2K1 w - - 0 1", "Qxd2"), 500 - 900);
+	}
+
+	#[test]
+	fn promotions_and_en_passant() {
+		assert_eq!(see("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q"), 800);
+		assert_eq!(see("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=Q"), 500 + 800);
+		// promoting onto a defended square loses the pawn
+		assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q"), -100);
+		assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=N"), -100);
+		// recapturing pawns promote as well
+		assert_eq!(
+			see("2B1k3/1Pq5/8/8/8/8/8/4K3 b - - 0 1", "Qxc8"),
+			300 - 900 - 800
+		);
+		assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"), 100);
+		assert_eq!(see("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"), 0);
+		let board = Board::from_fen("4k3/8/3p4/8/8/8/8/2R1K3 w - - 0 1");
+		let mov = Move::from_san(&board, "Rc5").unwrap();
+		assert!(board.see_ge(mov, -500));
+		assert!(!board.see_ge(mov, 0));
+	}
+}
@@ -1,5 +1,6 @@
 extern crate alloc;
 use super::*;
+use crate::consts::ZOBRIST_KEYS;
 use alloc::string::ToString;
 use rayon::prelude::*;
 
@@ -7,69 +8,43 @@ fn single_thread_perft(board: Board, depth: usize) -> usize {
 	if depth == 0 {
 		return 1;
 	}
-	let mut moves = vec![];
-	board.all_moves(|m| {
-		moves.push(m);
-		ops::ControlFlow::Continue(())
-	});
+	let moves = board.legal_moves();
 	if depth == 1 {
 		return moves.len();
 	}
-	let count = moves
+	moves
 		.iter()
 		.map(|mov| {
 			let mut board = board;
 			board.apply_move(*mov);
 			single_thread_perft(board, depth - 1)
 		})
-		.sum();
-	count
+		.sum()
 }
 
 fn perft(board: Board, depth: usize) -> usize {
 	if depth == 0 {
 		return 1;
 	}
-	let mut moves = vec![];
-	board.all_moves(|m| {
-		moves.push(m);
-		ops::ControlFlow::Continue(())
-	});
+	let moves = board.legal_moves();
 	if depth == 1 {
 		return moves.len();
 	}
-	let count = moves
+	moves
 		.par_iter()
 		.map(|mov| {
 			let mut board = board;
 			board.apply_move(*mov);
 			perft(board, depth - 1)
 		})
-		.sum();
-	count
+		.sum()
 }
 
 #[track_caller]
 fn assert_move(mut board: Board, mov: &str) -> Board {
-	let mut moves = vec![];
-	board.all_moves(|m| {
-		moves.push(m);
-		ops::ControlFlow::Continue(())
-	});
-	for m in moves.iter() {
-		if m.format(board, &moves).to_string() == mov {
-			board.apply_move(*m);
-			return board;
-		}
-	}
-	panic!(
-		"move not found: {mov} (found moves {:?})",
-		moves
-			.iter()
-			.map(|m| m.format(board, &moves).to_string())
-			.reduce(|a, b| format!("{a}, {b}"))
-			.unwrap_or("".to_string())
-	);
+	let m = Move::from_san(&board, mov).unwrap_or_else(|e| panic!("{e}: {mov}"));
+	board.apply_move(m);
+	board
 }
 
 #[track_caller]
@@ -88,16 +63,12 @@ fn assert_perft(board: Board, depth: usize, count: usize) {
 		return;
 	}
 	// try to find the move that caused the failure
-	let mut moves = vec![];
-	board.all_moves(|m| {
-		moves.push(m);
-		ops::ControlFlow::Continue(())
-	});
+	let moves = board.legal_moves();
 	for mov in moves.iter() {
 		let mut new_board = board;
 		new_board.apply_move(*mov);
 		let subcount = perft(new_board, depth - 1);
-		eprintln!("{}{}: {}", mov.from, mov.to, subcount);
+		eprintln!("{}{}: {}", mov.from(), mov.to(), subcount);
 	}
 	assert_eq!(actual, count, "perft failed at depth {depth}");
 }
@@ -158,7 +129,7 @@ fn initial_position() {
 
 #[test]
 fn position_2() {
-	let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -";
+	let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
 	let board = Board::from_fen(fen);
 	assert_eq!(board.to_fen(), fen);
 
@@ -213,7 +184,615 @@ fn benchmark() {
 
 #[test]
 fn fen_test() {
-	let fen = "rnbq3r/ppppkppp/5n2/2b1p3/2B1P3/5N2/PPPPKPPP/RNBQ3R w - -";
+	let fen = "rnbq3r/ppppkppp/5n2/2b1p3/2B1P3/5N2/PPPPKPPP/RNBQ3R w - - 4 6";
 	let b = Board::from_fen(fen);
-	assert_eq!(b.to_fen(), fen)
+	assert_eq!(b.to_fen(), fen);
+
+	// move counters are optional
+	let b = Board::from_fen("rnbq3r/ppppkppp/5n2/2b1p3/2B1P3/5N2/PPPPKPPP/RNBQ3R w - -");
+	assert_eq!(
+		b.to_fen(),
+		"rnbq3r/ppppkppp/5n2/2b1p3/2B1P3/5N2/PPPPKPPP/RNBQ3R w - - 0 1"
+	);
+}
+
+#[test]
+fn move_counters() {
+	let board = Board::initial_position();
+	assert_eq!((board.halfmove_clock(), board.fullmove_number()), (0, 1));
+	let board = assert_moves(board, &["Nf3", "Nc6", "Ng1"]);
+	assert_eq!(
+		board.to_fen(),
+		"r1bqkbnr/pppppppp/2n5/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 3 2"
+	);
+	let board = assert_moves(board, &["e5"]);
+	assert_eq!((board.halfmove_clock(), board.fullmove_number()), (0, 3));
+	let board = assert_moves(board, &["Nf3", "Nd4", "Nxd4"]);
+	assert_eq!(
+		board.to_fen(),
+		"r1bqkbnr/pppp1ppp/8/4p3/3N4/8/PPPPPPPP/RNBQKB1R b KQkq - 0 4"
+	);
+}
+
+#[test]
+fn fen_errors() {
+	#[track_caller]
+	fn assert_fen_error(fen: &str, field: FenField, offset: usize) {
+		let err = Board::try_from_fen(fen).unwrap_err();
+		assert_eq!((err.field, err.offset), (field, offset), "{err}");
+	}
+
+	let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+	assert!(start.parse::<Board>().is_ok());
+	assert_fen_error("", FenField::Placement, 0);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq -",
+		FenField::Placement,
+		34,
+	);
+	assert_fen_error(
+		"rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+		FenField::Placement,
+		13,
+	);
+	assert_fen_error(
+		"rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+		FenField::Placement,
+		17,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+		FenField::SideToMove,
+		43,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq -",
+		FenField::SideToMove,
+		44,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq -",
+		FenField::Castling,
+		48,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KK -",
+		FenField::Castling,
+		47,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq",
+		FenField::EnPassant,
+		50,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9",
+		FenField::EnPassant,
+		51,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3",
+		FenField::EnPassant,
+		51,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+		FenField::HalfmoveClock,
+		53,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - +0 1",
+		FenField::HalfmoveClock,
+		53,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+		FenField::FullmoveNumber,
+		55,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 +1",
+		FenField::FullmoveNumber,
+		55,
+	);
+	assert_fen_error(
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 garbage extra",
+		FenField::FullmoveNumber,
+		57,
+	);
+}
+
+#[test]
+fn insufficient_material() {
+	for fen in [
+		"8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+		"8/8/4k3/8/8/3K4/5B2/8 w - - 0 1",
+		"8/8/4k3/8/8/3K4/6n1/8 b - - 0 1",
+		"8/8/3bk3/8/8/3K4/5B2/8 w - - 0 1",
+		"8/8/4k3/2b5/8/3K4/5B2/B7 w - - 0 1",
+	] {
+		let board = Board::from_fen(fen);
+		assert!(board.has_insufficient_material(), "{fen}");
+		assert_eq!(
+			board.game_result(),
+			Some(GameResult::Draw {
+				draw: DrawReason::InsufficientMaterial
+			}),
+			"{fen}"
+		);
+	}
+	for fen in [
+		"8/8/4k3/8/8/3K4/5P2/8 w - - 0 1",
+		"8/8/4k3/8/8/3K4/5R2/8 w - - 0 1",
+		"8/8/2b1k3/8/8/3K4/5B2/8 w - - 0 1",
+		"8/8/4k3/8/8/3K4/5BN1/8 w - - 0 1",
+		"8/8/4k3/8/8/3K4/5NN1/8 w - - 0 1",
+		"8/8/2n1k3/8/8/3K4/5B2/8 w - - 0 1",
+	] {
+		let board = Board::from_fen(fen);
+		assert!(!board.has_insufficient_material(), "{fen}");
+		assert_eq!(board.game_result(), None, "{fen}");
+	}
+}
+
+#[test]
+fn zobrist_keys() {
+	let board = Board::initial_position();
+	assert_eq!(board.zobrist_key(), board.compute_zobrist_key());
+	let a = assert_moves(board, &["Nf3", "Nf6", "Nc3"]);
+	let b = assert_moves(board, &["Nc3", "Nf6", "Nf3"]);
+	assert_eq!(a.zobrist_key(), b.zobrist_key());
+	assert_ne!(a.zobrist_key(), board.zobrist_key());
+
+	// side to move
+	let c = Board::from_fen("rnbqkb1r/pppppppp/5n2/8/8/2N2N2/PPPPPPPP/R1BQKB1R w KQkq - 3 2");
+	assert_ne!(a.zobrist_key(), c.zobrist_key());
+	assert_eq!(a.zobrist_key(), c.zobrist_key() ^ ZOBRIST_KEYS[780]);
+
+	// the en passant file only counts if a capture is possible
+	let d = Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+	let e = Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1");
+	assert_eq!(d.zobrist_key(), e.zobrist_key());
+	let f = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+	let g = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1");
+	assert_ne!(f.zobrist_key(), g.zobrist_key());
+
+	// castling rights, including losing them when a rook is captured
+	let h = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+	let i = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1");
+	assert_ne!(h.zobrist_key(), i.zobrist_key());
+	let j = assert_moves(h, &["Rxa8+"]);
+	assert_eq!(j.to_fen(), "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1");
+	assert_eq!(j.zobrist_key(), j.compute_zobrist_key());
+
+	// apply_move checks the incremental key against a full recomputation in debug builds
+	let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -");
+	single_thread_perft(board, 3);
+}
+
+#[test]
+fn chess960_start_positions() {
+	let fen = |index| Board::chess960_position(index).to_fen();
+	assert_eq!(fen(518), Board::initial_position().to_fen());
+	assert_eq!(
+		fen(0),
+		"bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+	);
+	assert_eq!(
+		Board::chess960_position(0).to_shredder_fen(),
+		"bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+	);
+	assert_eq!(
+		fen(959),
+		"rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1"
+	);
+	let mut positions = std::collections::HashSet::new();
+	for index in 0..960 {
+		let board = Board::chess960_position(index);
+		assert_eq!(Board::from_fen(&board.to_fen()), board);
+		assert_eq!(Board::from_fen(&board.to_shredder_fen()), board);
+		positions.insert(board.to_fen());
+	}
+	assert_eq!(positions.len(), 960);
+}
+
+#[test]
+fn chess960_fen() {
+	// X-FEN uses file letters when the castling rook is not the outermost one
+	let fen = "1r2k1r1/8/8/8/8/8/8/R1R1K2R w CHg - 0 1";
+	let board = Board::from_fen(fen);
+	assert_eq!(board.to_fen(), "1r2k1r1/8/8/8/8/8/8/R1R1K2R w KCk - 0 1");
+	assert_eq!(
+		board.to_shredder_fen(),
+		"1r2k1r1/8/8/8/8/8/8/R1R1K2R w HCg - 0 1"
+	);
+	assert_eq!(Board::from_fen(&board.to_fen()), board);
+	assert_eq!(
+		Board::initial_position().to_shredder_fen(),
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+	);
+}
+
+#[test]
+fn chess960_castling() {
+	// king and rook swap places when castling kingside
+	let board = Board::from_fen("4k3/8/8/8/8/8/8/1R3KR1 w BG - 0 1");
+	let castled = assert_move(board, "O-O");
+	assert_eq!(castled.to_fen(), "4k3/8/8/8/8/8/8/1R3RK1 b - - 1 1");
+	let queenside = Move::from_san(&board, "O-O-O").unwrap();
+	assert_eq!(queenside.to_uci(), "f1b1");
+	assert_eq!(queenside.format_san(board, &[]).to_string(), "O-O-O");
+	assert!(!queenside.is_capture(&board));
+	let mut castled = board;
+	castled.apply_move(queenside);
+	assert_eq!(castled.to_fen(), "4k3/8/8/8/8/8/8/2KR2R1 b - - 1 1");
+
+	// the squares the rook passes through must be empty too
+	let board = Board::from_fen("4k3/8/8/8/8/8/8/RN2K3 w A - 0 1");
+	assert!(Move::from_san(&board, "O-O-O").is_err());
+
+	// the rook may shield the king's destination from an attack along the rank
+	let board = Board::from_fen("4k3/8/8/8/8/8/8/rRK5 w B - 0 1");
+	assert!(Move::from_san(&board, "O-O-O").is_err());
+
+	// standard castling in a Chess960 game can be written either way in UCI
+	let board = Board::chess960_position(518);
+	let board = assert_moves(board, &["e4", "e5", "Nf3", "Nf6", "Be2", "Be7"]);
+	let castle = Move::parse_uci(&board, "e1h1").unwrap();
+	assert_eq!(castle, Move::parse_uci(&board, "e1g1").unwrap());
+	assert_eq!(castle.to_uci_chess960(&board), "e1h1");
+}
+
+#[test]
+fn chess960_perft() {
+	for (fen, counts) in [
+		(
+			"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+			[21, 528, 12189, 326672],
+		),
+		(
+			"2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+			[21, 807, 18002, 667366],
+		),
+		(
+			"b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+			[20, 479, 10471, 273318],
+		),
+		(
+			"qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+			[22, 593, 13440, 382958],
+		),
+	] {
+		let board = Board::from_fen(fen);
+		assert_eq!(board.to_shredder_fen(), fen);
+		for (depth, count) in counts.into_iter().enumerate() {
+			assert_perft(board, depth + 1, count);
+		}
+	}
+}
+
+/// Generates legal moves the slow way, by trying each pseudo-legal move on a copy
+/// of the board, to check the legal move generator against.
+fn copy_and_test_moves(board: Board) -> Vec<Move> {
+	let player = board.current_player;
+	let mut moves = vec![];
+	for from in board.repr.player_pieces(player) {
+		let (_, piece) = board.getp(from).unwrap();
+		let mut targets = board.pseudo_legal_targets(from, (player, piece));
+		if piece == Piece::Pawn
+			&& let Some(target) = board.en_passant_target
+			&& target.file().value().abs_diff(from.file().value()) == 1
+			&& target.rank().value().abs_diff(from.rank().value()) == 1
+			&& board.getp(Pos::new(target.file(), from.rank())) == Some((!player, Piece::Pawn))
+		{
+			targets.set(target);
+		}
+		for to in targets {
+			let promotions = if piece == Piece::Pawn && matches!(to.rank(), Rank::One | Rank::Eight)
+			{
+				vec![
+					Some(Piece::Queen),
+					Some(Piece::Rook),
+					Some(Piece::Bishop),
+					Some(Piece::Knight),
+				]
+			} else {
+				vec![None]
+			};
+			for promotion in promotions {
+				moves.push(Move::new(from, to, promotion));
+			}
+		}
+	}
+	moves.retain(|&mov| {
+		let mut after = board;
+		after.apply_move(mov);
+		let king = after.repr.king_pos(player);
+		after
+			.attackers_with(king, !player, after.repr.occupied())
+			.is_zero()
+	});
+	// castling is generated separately and covered by the perft tests
+	board.all_moves(|mov| {
+		if board.castling_rook(mov).is_some() {
+			moves.push(mov);
+		}
+		ops::ControlFlow::Continue(())
+	});
+	moves
+}
+
+fn compare_with_copy_and_test(board: Board, depth: usize) {
+	let mut moves = board.legal_moves();
+	let mut expected = copy_and_test_moves(board);
+	let key = |m: &Move| {
+		(
+			m.from(),
+			m.to(),
+			m.promotion().map(|p| p.ascii_char(Player::White)),
+		)
+	};
+	moves.sort_by_key(key);
+	expected.sort_by_key(key);
+	assert_eq!(*moves, expected, "{}", board.to_fen());
+	if depth > 1 {
+		for mov in moves {
+			let mut board = board;
+			board.apply_move(mov);
+			compare_with_copy_and_test(board, depth - 1);
+		}
+	}
+}
+
+#[test]
+fn legal_move_generation() {
+	for fen in [
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+		"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+		"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+		"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+		"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+	] {
+		compare_with_copy_and_test(Board::from_fen(fen), 3);
+	}
+}
+
+#[test]
+fn en_passant_legality() {
+	let count = |fen| perft(Board::from_fen(fen), 1);
+	// capturing would expose the king to the queen along the rank
+	assert_eq!(count("8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1"), 6);
+	assert!(
+		Move::from_san(
+			&Board::from_fen("8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1"),
+			"exd3"
+		)
+		.is_err()
+	);
+	// the pawn is pinned diagonally and cannot capture off the pin line
+	assert!(
+		Move::from_san(
+			&Board::from_fen("7K/1k6/8/8/3Pp3/8/8/7B b - d3 0 1"),
+			"exd3"
+		)
+		.is_err()
+	);
+	// capturing the pawn that gives check is legal
+	let board = Board::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+	assert!(board.in_check());
+	assert_eq!(
+		Move::from_san(&board, "exd3").map(|m| m.to_uci()),
+		Ok("e4d3".to_string())
+	);
+	// but it does not resolve a check from another piece
+	let board = Board::from_fen("8/8/8/8/3Pp3/8/2k5/4N2K b - d3 0 1");
+	assert!(Move::from_san(&board, "exd3").is_err());
+	for fen in [
+		"8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1",
+		"7K/1k6/8/8/3Pp3/8/8/7B b - d3 0 1",
+		"8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+	] {
+		compare_with_copy_and_test(Board::from_fen(fen), 3);
+	}
+}
+
+#[test]
+fn perft_positions() {
+	for (fen, counts) in [
+		(
+			"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+			[14, 191, 2812, 43238],
+		),
+		(
+			"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+			[6, 264, 9467, 422333],
+		),
+		(
+			"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+			[44, 1486, 62379, 2103487],
+		),
+	] {
+		let board = Board::from_fen(fen);
+		for (depth, count) in counts.into_iter().enumerate() {
+			assert_perft(board, depth + 1, count);
+		}
+	}
+}
+
+/// Counts leaf nodes like [`perft`], but plays moves with `make_move` and checks that
+/// `unmake_move` restores the exact same board after every move.
+fn make_unmake_perft(board: &mut Board, depth: usize) -> usize {
+	if depth == 0 {
+		return 1;
+	}
+	let mut count = 0;
+	for mov in board.legal_moves() {
+		let before = *board;
+		let undo = board.make_move(mov);
+		let mut copied = before;
+		copied.apply_move(mov);
+		assert_eq!(*board, copied, "{} {}", before.to_fen(), mov.to_uci());
+		count += make_unmake_perft(board, depth - 1);
+		board.unmake_move(mov, undo);
+		assert_eq!(*board, before, "{} {}", before.to_fen(), mov.to_uci());
+	}
+	count
+}
+
+#[test]
+fn make_unmake() {
+	for (fen, depth, count) in [
+		(
+			"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+			3,
+			8902,
+		),
+		(
+			"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+			3,
+			97862,
+		),
+		("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+		(
+			"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+			3,
+			9467,
+		),
+		(
+			"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+			3,
+			62379,
+		),
+		(
+			"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+			3,
+			12189,
+		),
+		(
+			"b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+			3,
+			10471,
+		),
+	] {
+		let mut board = Board::from_fen(fen);
+		let start = board;
+		assert_eq!(make_unmake_perft(&mut board, depth), count, "{fen}");
+		assert_eq!(board, start);
+	}
+	let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 12 40");
+	let mov = Move::from_san(&board, "O-O").unwrap();
+	let undo = board.make_move(mov);
+	assert_eq!(undo.captured(), None);
+	assert_eq!(board.halfmove_clock(), 13);
+	board.unmake_move(mov, undo);
+	assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K2R w K - 12 40");
+}
+
+fn check_stages(board: Board, depth: usize) {
+	let captures = board.capture_moves();
+	let quiet = board.quiet_moves();
+	let all = board.legal_moves();
+	assert_eq!(
+		captures.len() + quiet.len(),
+		all.len(),
+		"{}",
+		board.to_fen()
+	);
+	for mov in &captures {
+		assert!(mov.is_capture(&board) || mov.promotion().is_some());
+		assert!(all.contains(mov));
+	}
+	for mov in &quiet {
+		assert!(!mov.is_capture(&board) && mov.promotion().is_none());
+		assert!(all.contains(mov));
+	}
+	if depth > 1 {
+		for mov in all {
+			let mut board = board;
+			board.apply_move(mov);
+			check_stages(board, depth - 1);
+		}
+	}
+}
+
+#[test]
+fn staged_move_generation() {
+	for fen in [
+		"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+		"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+		"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+		"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+	] {
+		check_stages(Board::from_fen(fen), 3);
+	}
+
+	let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+	let en_passant = Move::from_san(&board, "exd6").unwrap();
+	assert!(en_passant.is_capture(&board));
+	assert!(board.capture_moves().contains(&en_passant));
+	assert!(!board.quiet_moves().contains(&en_passant));
+
+	// quiet promotions are tactical, castling is quiet
+	let board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1");
+	assert_eq!(board.capture_moves().len(), 4);
+	assert!(
+		board
+			.quiet_moves()
+			.contains(&Move::from_san(&board, "O-O").unwrap())
+	);
+}
+
+fn check_flags(board: Board, depth: usize) {
+	for mov in board.legal_moves() {
+		let annotated = board.annotate(Move::new(mov.from(), mov.to(), mov.promotion()));
+		let flags = |m: Move| {
+			(
+				m.moving_piece(),
+				m.captured_piece(),
+				m.castling_side(),
+				m.is_en_passant(),
+				m.is_double_push(),
+			)
+		};
+		assert_eq!(flags(mov), flags(annotated), "{} {mov:?}", board.to_fen());
+		assert_eq!(mov.captured_piece().is_some(), mov.is_capture(&board));
+		if depth > 1 {
+			let mut board = board;
+			board.apply_move(mov);
+			check_flags(board, depth - 1);
+		}
+	}
+}
+
+#[test]
+fn move_flags() {
+	for fen in [
+		"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+		"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+		"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+		"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+	] {
+		check_flags(Board::from_fen(fen), 3);
+	}
+	assert_eq!(core::mem::size_of::<crate::mov::MoveFlags>(), 2);
+	assert_eq!(core::mem::size_of::<Move>(), 4);
+
+	let board = Board::from_fen("r3k2r/8/8/3pP3/8/8/1P6/R3K2R w KQkq d6 0 1");
+	let mov = |san| Move::from_san(&board, san).unwrap();
+	assert_eq!(mov("O-O").castling_side(), Some(CastlingSide::Kingside));
+	assert_eq!(mov("O-O-O").castling_side(), Some(CastlingSide::Queenside));
+	assert_eq!(mov("O-O").moving_piece(), Some(Piece::King));
+	assert!(mov("exd6").is_en_passant());
+	assert_eq!(mov("exd6").captured_piece(), Some(Piece::Pawn));
+	assert!(mov("b4").is_double_push() && !mov("b3").is_double_push());
+	assert_eq!(mov("Rxa8").captured_piece(), Some(Piece::Rook));
+	assert_eq!(mov("Ra7").captured_piece(), None);
+	// flags do not take part in comparisons
+	assert_eq!(mov("O-O"), Move::from_uci("e1g1"));
+	assert_eq!(Move::from_uci("e1g1").moving_piece(), None);
+	assert_eq!(Move::parse_uci(&board, "e1h1").unwrap(), mov("O-O"));
+	assert_eq!(
+		Move::parse_uci(&board, "e5d6").unwrap().captured_piece(),
+		Some(Piece::Pawn)
+	);
 }
@@ -0,0 +1,262 @@
+use crate::*;
+use core::fmt;
+
+/// A reason why a position cannot arise in a legal game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+	/// The given player has no king
+	MissingKing(Player),
+	/// A pawn stands on the first or last rank
+	PawnOnBackRank(Pos),
+	/// The player who just moved left their king in check
+	OpponentInCheck,
+	/// The given castling right has no king or rook in place to castle with
+	InvalidCastlingRight(Player, CastlingSide),
+	/// No pawn can have just made a double push past the en passant target
+	InvalidEnPassant(Pos),
+}
+
+impl PositionError {
+	/// Returns a short description of this error
+	pub fn message(&self) -> &'static str {
+		match self {
+			PositionError::MissingKing(Player::White) => "white has no king",
+			PositionError::MissingKing(Player::Black) => "black has no king",
+			PositionError::PawnOnBackRank(_) => "pawn on the first or last rank",
+			PositionError::OpponentInCheck => "the side not to move is in check",
+			PositionError::InvalidCastlingRight(..) => "castling right without a king and rook",
+			PositionError::InvalidEnPassant(_) => "en passant target without a pawn to capture",
+		}
+	}
+
+	/// Returns the square this error refers to, if any
+	pub fn square(&self) -> Option<Pos> {
+		match self {
+			PositionError::PawnOnBackRank(pos) | PositionError::InvalidEnPassant(pos) => Some(*pos),
+			_ => None,
+		}
+	}
+
+	/// Returns the FEN field this error would be found in
+	pub(super) fn fen_field(&self) -> FenField {
+		match self {
+			PositionError::MissingKing(_) | PositionError::PawnOnBackRank(_) => FenField::Placement,
+			PositionError::OpponentInCheck => FenField::SideToMove,
+			PositionError::InvalidCastlingRight(..) => FenField::Castling,
+			PositionError::InvalidEnPassant(_) => FenField::EnPassant,
+		}
+	}
+}
+
+impl fmt::Display for PositionError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PositionError::InvalidCastlingRight(player, side) => {
+				write!(f, "{} ({player} {side:?})", self.message())
+			}
+			_ => match self.square() {
+				Some(pos) => write!(f, "{} at {pos}", self.message()),
+				None => write!(f, "{}", self.message()),
+			},
+		}
+	}
+}
+
+impl std::error::Error for PositionError {}
+
+impl Board {
+	/// Checks that this position could arise in a legal game, returning every
+	/// problem found. Boards that fail this check may panic during move generation.
+	pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+		let mut errors = Vec::new();
+
+		for player in [Player::White, Player::Black] {
+			if self.repr.king(player).is_none() {
+				errors.push(PositionError::MissingKing(player));
+			}
+		}
+
+		let pawns = self.repr.pieces(Player::White, Piece::Pawn)
+			| self.repr.pieces(Player::Black, Piece::Pawn);
+		for pos in pawns {
+			if matches!(pos.rank(), Rank::One | Rank::Eight) {
+				errors.push(PositionError::PawnOnBackRank(pos));
+			}
+		}
+
+		let opponent = !self.current_player;
+		if let Some(king) = self.repr.king(opponent)
+			&& self.is_attacked(king, self.current_player)
+		{
+			errors.push(PositionError::OpponentInCheck);
+		}
+
+		for player in [Player::White, Player::Black] {
+			let back_rank = match player {
+				Player::White => Rank::One,
+				Player::Black => Rank::Eight,
+			};
+			let (kingside_castle, queenside_castle) = self.castling_rights(player);
+			for (rook_file, side) in [
+				(kingside_castle, CastlingSide::Kingside),
+				(queenside_castle, CastlingSide::Queenside),
+			] {
+				let Some(rook_file) = rook_file else {
+					continue;
+				};
+				let valid = self.repr.king(player).is_some_and(|king| {
+					king.rank() == back_rank
+						&& (rook_file > king.file()) == (side == CastlingSide::Kingside)
+						&& rook_file != king.file()
+				}) && self.getp(Pos::new(rook_file, back_rank))
+					== Some((player, Piece::Rook));
+				if !valid {
+					errors.push(PositionError::InvalidCastlingRight(player, side));
+				}
+			}
+		}
+
+		if let Some(target) = self.en_passant_target
+			&& !self.en_passant_is_possible(target)
+		{
+			errors.push(PositionError::InvalidEnPassant(target));
+		}
+
+		if errors.is_empty() {
+			Ok(())
+		} else {
+			Err(errors)
+		}
+	}
+
+	/// Returns true if the opponent can have just pushed a pawn two squares past
+	/// the given en passant target.
+	fn en_passant_is_possible(&self, target: Pos) -> bool {
+		let (target_rank, from_rank, to_rank) = match self.current_player {
+			Player::White => (Rank::Six, Rank::Seven, Rank::Five),
+			Player::Black => (Rank::Three, Rank::Two, Rank::Four),
+		};
+		let file = target.file();
+		target.rank() == target_rank
+			&& self.getp(target).is_none()
+			&& self.getp(Pos::new(file, from_rank)).is_none()
+			&& self.getp(Pos::new(file, to_rank)) == Some((!self.current_player, Piece::Pawn))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn errors(fen: &str) -> Vec<PositionError> {
+		let board = Board::try_from_fen_unvalidated(fen).unwrap();
+		board.validate().err().unwrap_or_default()
+	}
+
+	fn pos(s: &str) -> Pos {
+		Pos::try_from(s).unwrap()
+	}
+
+	#[test]
+	fn valid_positions() {
+		assert_eq!(Board::initial_position().validate(), Ok(()));
+		assert_eq!(Board::chess960_position(518).validate(), Ok(()));
+		assert_eq!(
+			errors("rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2"),
+			[]
+		);
+	}
+
+	#[test]
+	fn kings() {
+		assert_eq!(
+			errors("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+			[PositionError::MissingKing(Player::Black)]
+		);
+		assert_eq!(
+			errors("8/8/8/8/8/8/8/8 w - - 0 1"),
+			[
+				PositionError::MissingKing(Player::White),
+				PositionError::MissingKing(Player::Black)
+			]
+		);
+		assert!(Board::empty().validate().is_err());
+		assert_eq!(Board::empty().getp(pos("e1")), None);
+		assert_eq!(Board::empty().getp(pos("e8")), None);
+	}
+
+	#[test]
+	fn pawns_and_checks() {
+		assert_eq!(
+			errors("P3k3/8/8/8/8/8/8/4K2p w - - 0 1"),
+			[
+				PositionError::PawnOnBackRank(pos("a8")),
+				PositionError::PawnOnBackRank(pos("h1"))
+			]
+		);
+		assert_eq!(
+			errors("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+			[PositionError::OpponentInCheck]
+		);
+		assert_eq!(errors("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1"), []);
+	}
+
+	#[test]
+	fn castling_rights() {
+		assert_eq!(
+			errors("r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1"),
+			[
+				PositionError::InvalidCastlingRight(Player::White, CastlingSide::Queenside),
+				PositionError::InvalidCastlingRight(Player::Black, CastlingSide::Kingside)
+			]
+		);
+		assert_eq!(
+			errors("4k3/8/8/8/8/8/4K3/R6R w KQ - 0 1"),
+			[
+				PositionError::InvalidCastlingRight(Player::White, CastlingSide::Kingside),
+				PositionError::InvalidCastlingRight(Player::White, CastlingSide::Queenside)
+			]
+		);
+	}
+
+	#[test]
+	fn en_passant() {
+		assert_eq!(
+			errors("4k3/8/8/8/8/8/8/4K3 b - e3 0 1"),
+			[PositionError::InvalidEnPassant(pos("e3"))]
+		);
+		assert_eq!(
+			errors("4k3/8/8/8/4P3/4N3/8/4K3 b - e3 0 1"),
+			[PositionError::InvalidEnPassant(pos("e3"))]
+		);
+		let mut board = Board::try_from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+		board.en_passant_target = Some(pos("e6"));
+		assert_eq!(
+			board.validate(),
+			Err(vec![PositionError::InvalidEnPassant(pos("e6"))])
+		);
+	}
+
+	#[test]
+	fn fen_errors() {
+		let error = Board::try_from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap_err();
+		assert_eq!(error.field, FenField::Placement);
+		assert_eq!(error.message, "black has no king");
+
+		// a second king can't be placed on the board, so it is rejected while parsing
+		let error = Board::try_from_fen_unvalidated("KK6/8/8/8/8/8/8/7k w - - 0 1").unwrap_err();
+		assert_eq!(error.field, FenField::Placement);
+		assert_eq!(error.offset, 1);
+		assert_eq!(error.message, "more than one king of the same colour");
+		assert!(Board::try_from_fen("K6K/8/8/8/8/8/8/7k w - - 0 1").is_err());
+		assert!(Board::try_from_fen("K6k/8/8/8/8/8/8/k7 w - - 0 1").is_err());
+
+		let error = Board::try_from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").unwrap_err();
+		assert_eq!(error.field, FenField::Castling);
+		assert_eq!(error.offset, 22);
+
+		let error = Board::try_from_fen("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1").unwrap_err();
+		assert_eq!(error.field, FenField::SideToMove);
+		assert_eq!(error.offset, 22);
+	}
+}
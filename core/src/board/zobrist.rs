@@ -0,0 +1,71 @@
+use crate::consts::ZOBRIST_KEYS;
+use crate::*;
+
+const CASTLING_KEYS: usize = 768;
+const EN_PASSANT_KEYS: usize = 772;
+const TURN_KEY: usize = 780;
+
+/// Key for the given piece on the given square, ordered like Polyglot's keys (black
+/// pawn, white pawn, black knight, ..., white king). The key values themselves are
+/// not Polyglot's.
+pub(super) fn piece_key((player, piece): (Player, Piece), pos: Pos) -> u64 {
+	let kind = match piece {
+		Piece::Pawn => 0,
+		Piece::Knight => 1,
+		Piece::Bishop => 2,
+		Piece::Rook => 3,
+		Piece::Queen => 4,
+		Piece::King => 5,
+	} * 2 + match player {
+		Player::White => 1,
+		Player::Black => 0,
+	};
+	ZOBRIST_KEYS[64 * kind + 8 * pos.rank().value() as usize + pos.file().value() as usize]
+}
+
+impl Board {
+	/// Computes the Zobrist key of this position from scratch.
+	pub(super) fn compute_zobrist_key(&self) -> u64 {
+		let mut key = 0;
+		for i in 0..64 {
+			let pos = Pos::from_value(i);
+			if let Some(piece) = self.getp(pos) {
+				key ^= piece_key(piece, pos);
+			}
+		}
+		key ^ self.castling_key() ^ self.en_passant_key() ^ self.turn_key()
+	}
+
+	pub(super) fn castling_key(&self) -> u64 {
+		let mut key = 0;
+		for (i, right) in [
+			self.white_kingside_castle,
+			self.white_queenside_castle,
+			self.black_kingside_castle,
+			self.black_queenside_castle,
+		]
+		.into_iter()
+		.enumerate()
+		{
+			if right.is_some() {
+				key ^= ZOBRIST_KEYS[CASTLING_KEYS + i];
+			}
+		}
+		key
+	}
+
+	/// Like Polyglot, the en passant file is only hashed if a pawn could capture.
+	pub(super) fn en_passant_key(&self) -> u64 {
+		match self.en_passant_capture() {
+			Some(pos) => ZOBRIST_KEYS[EN_PASSANT_KEYS + pos.file().value() as usize],
+			None => 0,
+		}
+	}
+
+	pub(super) fn turn_key(&self) -> u64 {
+		match self.current_player {
+			Player::White => ZOBRIST_KEYS[TURN_KEY],
+			Player::Black => 0,
+		}
+	}
+}
@@ -0,0 +1,229 @@
+use crate::{Board, DrawReason, GameResult, Move, Player, WinReason};
+
+/// A game of chess, consisting of a starting position and the moves played from it.
+#[derive(Debug, Clone)]
+pub struct Game {
+	/// Every position reached so far, starting with the initial position
+	positions: Vec<Board>,
+	moves: Vec<Move>,
+	/// Result of the game if it ended by resignation, agreement or a claimed draw
+	outcome: Option<GameResult>,
+}
+
+impl Game {
+	pub fn new(start: Board) -> Self {
+		Self {
+			positions: vec![start],
+			moves: vec![],
+			outcome: None,
+		}
+	}
+
+	pub fn initial_position() -> Self {
+		Self::new(Board::initial_position())
+	}
+
+	/// The position the game started from
+	pub fn start(&self) -> &Board {
+		&self.positions[0]
+	}
+
+	/// The current position
+	pub fn board(&self) -> &Board {
+		self.positions.last().expect("game has no positions")
+	}
+
+	/// All moves played so far, in order
+	pub fn moves(&self) -> &[Move] {
+		&self.moves
+	}
+
+	/// Plays the given move, which must be legal in the current position.
+	pub fn play(&mut self, mov: Move) {
+		let mut board = *self.board();
+		board.apply_move(mov);
+		self.positions.push(board);
+		self.moves.push(mov);
+	}
+
+	/// Takes back the last move, returning it. Also reverts any resignation,
+	/// draw agreement or claimed draw.
+	pub fn undo(&mut self) -> Option<Move> {
+		let mov = self.moves.pop()?;
+		self.positions.pop();
+		self.outcome = None;
+		Some(mov)
+	}
+
+	/// Returns how many times the current position has occurred in this game,
+	/// including the current occurrence.
+	pub fn position_count(&self) -> usize {
+		let board = self.board();
+		// positions before the last capture or pawn move cannot repeat
+		let reversible = (board.halfmove_clock() as usize).min(self.positions.len() - 1);
+		self.positions[self.positions.len() - 1 - reversible..]
+			.iter()
+			.rev()
+			.step_by(2)
+			.filter(|p| p.same_position(board))
+			.count()
+	}
+
+	/// Returns the draw the current player could claim, if any.
+	pub fn claimable_draw(&self) -> Option<DrawReason> {
+		if self.position_count() >= 3 {
+			Some(DrawReason::ThreefoldRepetition)
+		} else if self.board().halfmove_clock() >= 100 {
+			Some(DrawReason::FiftyMoveRule)
+		} else {
+			None
+		}
+	}
+
+	/// Claims a draw by threefold repetition or the fifty move rule, returning the
+	/// reason if the claim was valid.
+	pub fn claim_draw(&mut self) -> Option<DrawReason> {
+		if self.result().is_some() {
+			return None;
+		}
+		let draw = self.claimable_draw()?;
+		self.outcome = Some(GameResult::Draw { draw });
+		Some(draw)
+	}
+
+	pub fn agree_draw(&mut self) {
+		if self.result().is_none() {
+			self.outcome = Some(GameResult::Draw {
+				draw: DrawReason::Agreement,
+			});
+		}
+	}
+
+	pub fn resign(&mut self, player: Player) {
+		if self.result().is_none() {
+			self.outcome = Some(GameResult::Win {
+				winner: !player,
+				win: WinReason::Resignation,
+			});
+		}
+	}
+
+	/// Returns the result of the game if it is over. Besides checkmate, stalemate and
+	/// any draw or resignation recorded on this game, this returns the automatic draws
+	/// after fivefold repetition and after 75 moves without a capture or pawn move.
+	pub fn result(&self) -> Option<GameResult> {
+		if let Some(outcome) = self.outcome {
+			return Some(outcome);
+		}
+		if let Some(result) = self.board().game_result() {
+			return Some(result);
+		}
+		if self.position_count() >= 5 {
+			Some(GameResult::Draw {
+				draw: DrawReason::ThreefoldRepetition,
+			})
+		} else if self.board().halfmove_clock() >= 150 {
+			Some(GameResult::Draw {
+				draw: DrawReason::FiftyMoveRule,
+			})
+		} else {
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Board, DrawReason, Game, GameResult, Move, Player, WinReason};
+
+	fn uci(game: &mut Game, moves: &str) {
+		for mov in moves.split(' ') {
+			game.play(Move::from_uci(mov));
+		}
+	}
+
+	#[test]
+	fn threefold_repetition() {
+		let mut game = Game::initial_position();
+		assert_eq!(game.position_count(), 1);
+		uci(&mut game, "g1f3 g8f6 f3g1 f6g8");
+		assert_eq!(game.position_count(), 2);
+		assert_eq!(game.claimable_draw(), None);
+		uci(&mut game, "g1f3 g8f6 f3g1 f6g8");
+		assert_eq!(game.position_count(), 3);
+		assert_eq!(game.result(), None);
+		assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+		uci(&mut game, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1");
+		assert_eq!(game.result(), None);
+		uci(&mut game, "f6g8");
+		assert_eq!(
+			game.result(),
+			Some(GameResult::Draw {
+				draw: DrawReason::ThreefoldRepetition
+			})
+		);
+		assert_eq!(game.undo(), Some(Move::from_uci("f6g8")));
+		assert_eq!(game.result(), None);
+		assert_eq!(game.claim_draw(), Some(DrawReason::ThreefoldRepetition));
+		assert!(game.result().is_some());
+	}
+
+	#[test]
+	fn en_passant_rights_affect_repetition() {
+		// after e4 the en passant square is set, but no black pawn can capture
+		let mut game = Game::initial_position();
+		uci(&mut game, "e2e4 g8f6 g1f3 f6g8 f3g1");
+		assert_eq!(game.position_count(), 2);
+
+		// here black can capture en passant after f4, so the first occurrence differs
+		let mut game = Game::new(Board::from_fen("4k3/8/8/8/4p3/8/5P2/4K3 w - - 0 1"));
+		uci(&mut game, "f2f4 e8d8 e1d1 d8e8 d1e1");
+		assert_eq!(game.position_count(), 1);
+		uci(&mut game, "e8d8 e1d1 d8e8 d1e1");
+		assert_eq!(game.position_count(), 2);
+	}
+
+	#[test]
+	fn fifty_move_rule() {
+		let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80"));
+		assert_eq!(game.claimable_draw(), None);
+		uci(&mut game, "a1a2");
+		assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+		assert_eq!(game.result(), None);
+
+		let game = Game::new(Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 150 100"));
+		assert_eq!(
+			game.result(),
+			Some(GameResult::Draw {
+				draw: DrawReason::FiftyMoveRule
+			})
+		);
+	}
+
+	#[test]
+	fn resignation_and_agreement() {
+		let mut game = Game::initial_position();
+		game.resign(Player::White);
+		assert_eq!(
+			game.result(),
+			Some(GameResult::Win {
+				winner: Player::Black,
+				win: WinReason::Resignation
+			})
+		);
+
+		let mut game = Game::initial_position();
+		uci(&mut game, "e2e4");
+		game.agree_draw();
+		assert_eq!(
+			game.result(),
+			Some(GameResult::Draw {
+				draw: DrawReason::Agreement
+			})
+		);
+		game.undo();
+		assert_eq!(game.result(), None);
+		assert_eq!(game.moves(), &[]);
+		assert_eq!(game.board().to_fen(), game.start().to_fen());
+	}
+}
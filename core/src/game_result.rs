@@ -2,7 +2,7 @@ use core::fmt;
 
 use crate::Player;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameResult {
 	Win { winner: Player, win: WinReason },
 	Draw { draw: DrawReason },
@@ -17,7 +17,7 @@ impl fmt::Display for GameResult {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DrawReason {
 	Stalemate,
 	InsufficientMaterial,
@@ -39,7 +39,7 @@ impl fmt::Display for DrawReason {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WinReason {
 	Checkmate,
 	Resignation,
@@ -4,24 +4,30 @@ mod board;
 mod consts;
 mod direction;
 mod file;
+mod game;
 mod game_result;
 mod mov;
+mod move_list;
+mod pgn;
 mod piece;
 mod player;
 mod pos;
 mod rank;
 
-pub use ai::search;
+pub use ai::{SearchLimits, SearchResult, Searcher, search};
 pub use bitboard::Bitboard;
-pub use board::Board;
+pub use board::{Board, BoardBuilder, FenError, FenField, PositionError, UndoInfo};
 pub use consts::{
 	ADJACENT_BITBOARDS, BISHOP_MOVE_BITBOARDS, BLACK_PAWN_CHECK_BITBOARDS, KNIGHT_BITBOARDS,
 	ROOK_MOVE_BITBOARDS, WHITE_PAWN_CHECK_BITBOARDS,
 };
 pub use direction::{ADJACENT_DIRECTIONS, DIAGONAL_DIRECTIONS, Direction, ORTHOGONAL_DIRECTIONS};
 pub use file::{FILES, File};
+pub use game::Game;
 pub use game_result::{DrawReason, GameResult, WinReason};
-pub use mov::Move;
+pub use mov::{CastlingSide, Move, SanError, UciMoveError};
+pub use move_list::{MoveList, MoveListIterator};
+pub use pgn::{PgnError, PgnGame, PgnMove, PgnReader, read_pgn, result_token, write_pgn};
 pub use piece::{HOME_ROW, Piece};
 pub use player::Player;
 pub use pos::Pos;
@@ -1,15 +1,41 @@
+mod flags;
+mod san;
+mod uci;
+
 use core::fmt;
 use std::ops;
 
-use crate::{Board, File, Piece, Pos};
+use crate::{Board, Piece, Pos};
+pub use flags::CastlingSide;
+pub(crate) use flags::MoveFlags;
+pub use san::SanError;
+pub use uci::UciMoveError;
+
+const TO_SHIFT: u32 = 6;
+const PROMOTION_SHIFT: u32 = 12;
+const FLAGS_SHIFT: u32 = 16;
+const SQUARE_BITS: u32 = 0b11_1111;
+/// The bits that identify a move: its squares and promotion
+const MOVE_BITS: u32 = (1 << 15) - 1;
+
+/// A move, along with flags describing it in the position it was generated for.
+/// Moves compare equal if they have the same squares and promotion, regardless
+/// of their flags.
+///
+/// Moves are packed into 32 bits to keep move lists small: the from square (bits
+/// 0-5), the to square (bits 6-11), the promotion piece (bits 12-14, zero if none)
+/// and the flags (bits 16-31).
+#[derive(Copy, Clone)]
+pub struct Move(u32);
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub struct Move {
-	pub from: Pos,
-	pub to: Pos,
-	pub promotion: Option<Piece>,
+impl PartialEq for Move {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 & MOVE_BITS == other.0 & MOVE_BITS
+	}
 }
 
+impl Eq for Move {}
+
 struct FormattedMove {
 	mov: Move,
 	piece: Piece,
@@ -21,11 +47,72 @@ struct FormattedMove {
 	en_passant: bool,
 	check: bool,
 	checkmate: bool,
+	/// Write castling as `O-O` and omit the `e.p.` suffix, as required by PGN
+	standard: bool,
 }
 
 impl Move {
+	/// Constructs a move without flags. Use [`Board::annotate`] to fill them in.
+	pub fn new(from: Pos, to: Pos, promotion: Option<Piece>) -> Self {
+		Self(
+			u32::from(from.value())
+				| (u32::from(to.value()) << TO_SHIFT)
+				| (u32::from(flags::encode(promotion)) << PROMOTION_SHIFT),
+		)
+		.with_flags(MoveFlags::UNKNOWN)
+	}
+
+	pub(crate) fn with_flags(self, flags: MoveFlags) -> Self {
+		Self((self.0 & MOVE_BITS) | (u32::from(flags.bits()) << FLAGS_SHIFT))
+	}
+
+	pub fn from(self) -> Pos {
+		Pos::from_value((self.0 & SQUARE_BITS) as u8)
+	}
+
+	pub fn to(self) -> Pos {
+		Pos::from_value(((self.0 >> TO_SHIFT) & SQUARE_BITS) as u8)
+	}
+
+	/// Returns the piece a pawn promotes to, if this move is a promotion
+	pub fn promotion(self) -> Option<Piece> {
+		flags::decode(((self.0 >> PROMOTION_SHIFT) & 0b111) as u16)
+	}
+
+	fn flags(self) -> MoveFlags {
+		MoveFlags::from_bits((self.0 >> FLAGS_SHIFT) as u16)
+	}
+
+	/// Returns the piece being moved, or `None` if this move has no flags, i.e. it
+	/// was neither generated by [`Board::all_moves`] nor passed to [`Board::annotate`].
+	pub fn moving_piece(self) -> Option<Piece> {
+		self.flags().piece()
+	}
+
+	/// Returns the piece captured by this move, including the pawn captured en
+	/// passant. Always `None` for moves without flags.
+	pub fn captured_piece(self) -> Option<Piece> {
+		self.flags().captured()
+	}
+
+	/// Returns the side this move castles on, if it is castling
+	pub fn castling_side(self) -> Option<CastlingSide> {
+		self.flags().castling_side()
+	}
+
+	/// Returns true if this move is an en passant capture
+	pub fn is_en_passant(self) -> bool {
+		self.flags().is_en_passant()
+	}
+
+	/// Returns true if this move advances a pawn by two squares
+	pub fn is_double_push(self) -> bool {
+		self.flags().is_double_push()
+	}
+
+	/// Returns true if this move captures a piece, including en passant captures.
 	pub fn is_capture(self, board: &Board) -> bool {
-		board.getp(self.to).is_some()
+		board.annotate(self).captured_piece().is_some()
 	}
 
 	pub fn format(
@@ -33,7 +120,22 @@ impl Move {
 		board: Board,
 		all_moves: &[Move],
 	) -> impl fmt::Display + Send + Sync + use<> {
-		let (player, piece) = board.getp(self.from).expect("no piece at from");
+		self.formatted(board, all_moves, false)
+	}
+
+	/// Like [`Move::format`], but formats castling as `O-O`/`O-O-O` and omits the
+	/// `e.p.` suffix, as required for PGN movetext.
+	pub fn format_san(
+		self,
+		board: Board,
+		all_moves: &[Move],
+	) -> impl fmt::Display + Send + Sync + use<> {
+		self.formatted(board, all_moves, true)
+	}
+
+	fn formatted(self, board: Board, all_moves: &[Move], standard: bool) -> FormattedMove {
+		let mov = board.annotate(self);
+		let (player, piece) = board.getp(self.from()).expect("no piece at from");
 
 		let (check, checkmate) = {
 			let mut new_board: Board = board;
@@ -55,24 +157,24 @@ impl Move {
 		let mut specify_rank = false;
 		let mut specify_file = false;
 		for mov in all_moves {
-			if mov.from == self.from {
+			if mov.from() == self.from() {
 				// same origin square, cannot be ambiguous
 				continue;
 			}
-			if (player, piece) != board.getp(mov.from).expect("no piece at from") {
+			if (player, piece) != board.getp(mov.from()).expect("no piece at from") {
 				// different piece (and/or different player)
 				continue;
 			}
-			if self.to != mov.to {
+			if self.to() != mov.to() {
 				// different target square
 				continue;
 			}
 			// we have a different move to the same square with an identical piece
 			specify_something = true;
-			if self.from.file() == mov.from.file() {
+			if self.from().file() == mov.from().file() {
 				specify_rank = true;
 			}
-			if self.from.rank() == mov.from.rank() {
+			if self.from().rank() == mov.from().rank() {
 				specify_file = true;
 			}
 		}
@@ -80,75 +182,88 @@ impl Move {
 			specify_file = true;
 		}
 
-		let en_passant = piece == Piece::Pawn
-			&& self.from.file() != self.to.file()
-			&& board.getp(self.to).is_none();
 		FormattedMove {
-			mov: self,
+			mov,
 			piece,
-			capture: board.getp(self.to).is_some() || en_passant,
+			capture: mov.captured_piece().is_some(),
 			specify_file,
 			specify_rank,
-			kingside: piece == Piece::King
-				&& self.from.file() == File::E
-				&& self.to.file() == File::G,
-			queenside: piece == Piece::King
-				&& self.from.file() == File::E
-				&& self.to.file() == File::C,
-			en_passant,
+			kingside: mov.castling_side() == Some(CastlingSide::Kingside),
+			queenside: mov.castling_side() == Some(CastlingSide::Queenside),
+			en_passant: mov.is_en_passant(),
 			check,
 			checkmate,
+			standard,
 		}
 	}
 
+	/// Constructs a move from UCI notation without any validation, panicking on
+	/// malformed input. Use [`Move::parse_uci`] for untrusted input.
 	pub fn from_uci(uci: &str) -> Self {
 		let from_str = uci.get(0..2).unwrap();
 		let from = Pos::try_from(from_str).unwrap();
 		let to_str = uci.get(2..4).unwrap();
 		let to = Pos::try_from(to_str).unwrap();
 		let promotion = uci.chars().nth(4).map(|c| Piece::from_ascii_char(c).1);
-		Self {
-			from,
-			to,
-			promotion,
-		}
+		Self::new(from, to, promotion)
 	}
 
 	pub fn to_uci(&self) -> String {
 		let mut uci = String::with_capacity(5);
-		uci.push_str(self.from.to_string().as_str());
-		uci.push_str(self.to.to_string().as_str());
-		if let Some(promotion) = self.promotion {
+		uci.push_str(self.from().to_string().as_str());
+		uci.push_str(self.to().to_string().as_str());
+		if let Some(promotion) = self.promotion() {
 			uci.push(promotion.ascii_char(crate::Player::Black));
 		}
 		uci
 	}
+
+	/// Like [`Move::to_uci`], but writes castling as the king capturing its own
+	/// rook (e.g. `e1h1`), as expected by Chess960-aware programs.
+	pub fn to_uci_chess960(&self, board: &Board) -> String {
+		match board.castling_rook(*self) {
+			Some(rook) => Move::new(self.from(), rook, self.promotion()).to_uci(),
+			None => self.to_uci(),
+		}
+	}
+}
+
+impl fmt::Debug for Move {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Move")
+			.field("from", &self.from())
+			.field("to", &self.to())
+			.field("promotion", &self.promotion())
+			.field("flags", &self.flags())
+			.finish()
+	}
 }
 
 impl fmt::Display for FormattedMove {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let castle = if self.standard { 'O' } else { '0' };
 		if self.kingside {
-			write!(f, "0-0")?;
+			write!(f, "{castle}-{castle}")?;
 		} else if self.queenside {
-			write!(f, "0-0-0")?;
+			write!(f, "{castle}-{castle}-{castle}")?;
 		} else {
 			if self.piece != Piece::Pawn {
 				write!(f, "{}", self.piece.notation())?;
 				if self.specify_file {
-					write!(f, "{}", self.mov.from.file())?;
+					write!(f, "{}", self.mov.from().file())?;
 				}
 				if self.specify_rank {
-					write!(f, "{}", self.mov.from.rank())?;
+					write!(f, "{}", self.mov.from().rank())?;
 				}
 			}
 			if self.capture {
 				if self.piece == Piece::Pawn {
-					write!(f, "{}", self.mov.from.file())?;
+					write!(f, "{}", self.mov.from().file())?;
 				}
 				write!(f, "x")?;
 			}
-			write!(f, "{}", self.mov.to)?;
-			if let Some(p) = self.mov.promotion {
+			write!(f, "{}", self.mov.to())?;
+			if let Some(p) = self.mov.promotion() {
 				write!(f, "={}", p.notation())?;
 			}
 		}
@@ -157,7 +272,7 @@ impl fmt::Display for FormattedMove {
 		} else if self.check {
 			write!(f, "+")?;
 		}
-		if self.en_passant {
+		if self.en_passant && !self.standard {
 			write!(f, " e.p.")?;
 		}
 		Ok(())
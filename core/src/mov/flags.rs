@@ -0,0 +1,132 @@
+use crate::Piece;
+
+/// The side of the board a castling move is played on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastlingSide {
+	Kingside,
+	Queenside,
+}
+
+const PIECE_BITS: u16 = 0b111;
+const CAPTURED_SHIFT: u16 = 3;
+const EN_PASSANT: u16 = 1 << 6;
+const DOUBLE_PUSH: u16 = 1 << 7;
+const KINGSIDE_CASTLE: u16 = 1 << 8;
+const QUEENSIDE_CASTLE: u16 = 1 << 9;
+
+/// Information about a move that depends on the position it is played in, packed
+/// into 16 bits: the moving piece (bits 0-2, zero if unknown), the captured piece
+/// (bits 3-5, zero if none), then en passant, double pawn push, kingside castling
+/// and queenside castling (bits 6-9).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct MoveFlags(u16);
+
+pub(super) fn encode(piece: Option<Piece>) -> u16 {
+	piece.map_or(0, |piece| piece as u16 + 1)
+}
+
+pub(super) fn decode(bits: u16) -> Option<Piece> {
+	Some(match bits {
+		0 => return None,
+		1 => Piece::Pawn,
+		2 => Piece::Knight,
+		3 => Piece::Bishop,
+		4 => Piece::Rook,
+		5 => Piece::Queen,
+		_ => Piece::King,
+	})
+}
+
+impl MoveFlags {
+	/// Flags for a move whose piece is not known
+	pub(crate) const UNKNOWN: Self = Self(0);
+
+	pub(super) fn from_bits(bits: u16) -> Self {
+		Self(bits)
+	}
+
+	pub(super) fn bits(self) -> u16 {
+		self.0
+	}
+
+	pub(crate) fn new(piece: Piece, captured: Option<Piece>) -> Self {
+		Self(encode(Some(piece)) | (encode(captured) << CAPTURED_SHIFT))
+	}
+
+	pub(crate) fn castling(side: CastlingSide) -> Self {
+		let flag = match side {
+			CastlingSide::Kingside => KINGSIDE_CASTLE,
+			CastlingSide::Queenside => QUEENSIDE_CASTLE,
+		};
+		Self(Self::new(Piece::King, None).0 | flag)
+	}
+
+	pub(crate) fn en_passant() -> Self {
+		Self(Self::new(Piece::Pawn, Some(Piece::Pawn)).0 | EN_PASSANT)
+	}
+
+	pub(crate) fn double_push() -> Self {
+		Self(Self::new(Piece::Pawn, None).0 | DOUBLE_PUSH)
+	}
+
+	pub(crate) fn piece(self) -> Option<Piece> {
+		decode(self.0 & PIECE_BITS)
+	}
+
+	pub(crate) fn captured(self) -> Option<Piece> {
+		decode((self.0 >> CAPTURED_SHIFT) & PIECE_BITS)
+	}
+
+	pub(crate) fn is_en_passant(self) -> bool {
+		self.0 & EN_PASSANT != 0
+	}
+
+	pub(crate) fn is_double_push(self) -> bool {
+		self.0 & DOUBLE_PUSH != 0
+	}
+
+	pub(crate) fn castling_side(self) -> Option<CastlingSide> {
+		if self.0 & KINGSIDE_CASTLE != 0 {
+			Some(CastlingSide::Kingside)
+		} else if self.0 & QUEENSIDE_CASTLE != 0 {
+			Some(CastlingSide::Queenside)
+		} else {
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn packing() {
+		let pieces = [
+			Piece::Pawn,
+			Piece::Knight,
+			Piece::Bishop,
+			Piece::Rook,
+			Piece::Queen,
+			Piece::King,
+		];
+		for piece in pieces {
+			for captured in pieces.into_iter().map(Some).chain([None]) {
+				let flags = MoveFlags::new(piece, captured);
+				assert_eq!(flags.piece(), Some(piece));
+				assert_eq!(flags.captured(), captured);
+				assert!(!flags.is_en_passant() && !flags.is_double_push());
+				assert_eq!(flags.castling_side(), None);
+			}
+		}
+		assert_eq!(MoveFlags::UNKNOWN.piece(), None);
+		let castling = MoveFlags::castling(CastlingSide::Queenside);
+		assert_eq!(castling.castling_side(), Some(CastlingSide::Queenside));
+		assert_eq!(castling.piece(), Some(Piece::King));
+		assert_eq!(castling.captured(), None);
+		assert!(MoveFlags::en_passant().is_en_passant());
+		assert_eq!(MoveFlags::en_passant().captured(), Some(Piece::Pawn));
+		assert!(MoveFlags::double_push().is_double_push());
+		assert!(!MoveFlags::double_push().is_en_passant());
+	}
+}
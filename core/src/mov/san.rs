@@ -0,0 +1,224 @@
+use crate::*;
+use core::{fmt, ops};
+
+/// Error returned when a move in Standard Algebraic Notation cannot be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanError {
+	/// The string is not syntactically valid SAN
+	Invalid,
+	/// No legal move matches the given SAN
+	Illegal,
+	/// More than one legal move matches the given SAN
+	Ambiguous,
+}
+
+impl fmt::Display for SanError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let s = match self {
+			SanError::Invalid => "invalid SAN move",
+			SanError::Illegal => "illegal move",
+			SanError::Ambiguous => "ambiguous move",
+		};
+		write!(f, "{s}")
+	}
+}
+
+impl std::error::Error for SanError {}
+
+/// The parts of a SAN move that can be matched against legal moves
+struct SanMove {
+	piece: Piece,
+	from_file: Option<File>,
+	from_rank: Option<Rank>,
+	to: Pos,
+	promotion: Option<Piece>,
+}
+
+fn parse_piece(ch: char) -> Option<Piece> {
+	Some(match ch {
+		'N' => Piece::Knight,
+		'B' => Piece::Bishop,
+		'R' => Piece::Rook,
+		'Q' => Piece::Queen,
+		'K' => Piece::King,
+		_ => return None,
+	})
+}
+
+fn parse_san_move(san: &str) -> Result<SanMove, SanError> {
+	let mut chars: Vec<char> = san.chars().collect();
+
+	let promotion = match chars.as_slice() {
+		[.., '=', p] | [.., '1'..='8', p] => {
+			let promotion = parse_piece(*p).filter(|&p| p != Piece::King);
+			let promotion = promotion.ok_or(SanError::Invalid)?;
+			chars.pop();
+			if chars.last() == Some(&'=') {
+				chars.pop();
+			}
+			Some(promotion)
+		}
+		_ => None,
+	};
+
+	let to = match chars.as_slice() {
+		[.., file, rank] => {
+			let file = File::try_from(*file).map_err(|_| SanError::Invalid)?;
+			let rank = Rank::try_from(*rank).map_err(|_| SanError::Invalid)?;
+			Pos::new(file, rank)
+		}
+		_ => return Err(SanError::Invalid),
+	};
+	chars.truncate(chars.len() - 2);
+	if matches!(chars.last(), Some('x' | ':')) {
+		chars.pop();
+	}
+
+	let mut rest = chars.as_slice();
+	let piece = match rest.first().copied().and_then(parse_piece) {
+		Some(piece) => {
+			rest = &rest[1..];
+			piece
+		}
+		None => Piece::Pawn,
+	};
+	let mut from_file = None;
+	let mut from_rank = None;
+	for &ch in rest {
+		match ch {
+			'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+				from_file = File::try_from(ch).ok();
+			}
+			'1'..='8' if from_rank.is_none() => {
+				from_rank = Rank::try_from(ch).ok();
+			}
+			_ => return Err(SanError::Invalid),
+		}
+	}
+	if piece != Piece::Pawn && promotion.is_some() {
+		return Err(SanError::Invalid);
+	}
+
+	Ok(SanMove {
+		piece,
+		from_file,
+		from_rank,
+		to,
+		promotion,
+	})
+}
+
+impl Move {
+	/// Parses a move in Standard Algebraic Notation, such as `Nbd7`, `exd6 e.p.`,
+	/// `e8=Q+` or `O-O-O`. Check and mate suffixes and annotation glyphs like `!?`
+	/// are accepted and ignored.
+	pub fn from_san(board: &Board, san: &str) -> Result<Self, SanError> {
+		let san = san.trim().trim_end_matches(['!', '?']);
+		let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+		let san = san.trim_end_matches(['+', '#']);
+
+		let castle = match san {
+			"O-O" | "0-0" => Some(CastlingSide::Kingside),
+			"O-O-O" | "0-0-0" => Some(CastlingSide::Queenside),
+			_ => None,
+		};
+		let san_move = match castle {
+			Some(_) => None,
+			None => Some(parse_san_move(san)?),
+		};
+
+		let mut result = Err(SanError::Illegal);
+		board.all_moves(|m| {
+			let matches = match &san_move {
+				None => m.castling_side() == castle,
+				Some(san_move) => {
+					m.moving_piece() == Some(san_move.piece)
+						&& m.to() == san_move.to
+						&& m.promotion() == san_move.promotion
+						&& san_move.from_file.is_none_or(|f| f == m.from().file())
+						&& san_move.from_rank.is_none_or(|r| r == m.from().rank())
+				}
+			};
+			if !matches {
+				return ops::ControlFlow::Continue(());
+			}
+			if result.is_ok() {
+				result = Err(SanError::Ambiguous);
+				return ops::ControlFlow::Break(());
+			}
+			result = Ok(m);
+			ops::ControlFlow::Continue(())
+		});
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Board, Move, SanError};
+
+	#[track_caller]
+	fn assert_san(fen: &str, san: &str, uci: &str) {
+		let board = Board::from_fen(fen);
+		assert_eq!(
+			Move::from_san(&board, san).map(|m| m.to_uci()),
+			Ok(uci.to_string())
+		);
+	}
+
+	#[track_caller]
+	fn assert_san_error(fen: &str, san: &str, error: SanError) {
+		let board = Board::from_fen(fen);
+		assert_eq!(Move::from_san(&board, san), Err(error));
+	}
+
+	#[test]
+	fn from_san() {
+		let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+		assert_san(start, "e4", "e2e4");
+		assert_san(start, "Nf3!?", "g1f3");
+		assert_san(start, "Nc3!", "b1c3");
+		assert_san_error(start, "e5", SanError::Illegal);
+		assert_san_error(start, "Nd2", SanError::Illegal);
+		assert_san_error(start, "O-O", SanError::Illegal);
+		assert_san_error(start, "Xe4", SanError::Invalid);
+		assert_san_error(start, "", SanError::Invalid);
+		assert_san_error(start, "e9", SanError::Invalid);
+		assert_san_error(start, "Ngxf3x", SanError::Invalid);
+
+		let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+		assert_san(kiwipete, "O-O", "e1g1");
+		assert_san(kiwipete, "0-0-0", "e1c1");
+		assert_san(kiwipete, "Qxf6", "f3f6");
+		assert_san(kiwipete, "Bxa6", "e2a6");
+		assert_san(kiwipete, "dxe6", "d5e6");
+		assert_san(kiwipete, "gxh3", "g2h3");
+		assert_san(kiwipete, "Nxf7", "e5f7");
+		assert_san(kiwipete, "Nb5", "c3b5");
+		assert_san(kiwipete, "Ncb5", "c3b5");
+		assert_san_error(kiwipete, "Neb5", SanError::Illegal);
+
+		let en_passant = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1";
+		assert_san(en_passant, "bxa3 e.p.", "b4a3");
+		assert_san(en_passant, "bxa3", "b4a3");
+		assert_san(en_passant, "bxc3", "b4c3");
+		assert_san(en_passant, "Bxe2", "a6e2");
+
+		let promotion = "1r2k3/2P5/8/8/8/8/8/4K3 w - - 0 1";
+		assert_san(promotion, "c8=Q+", "c7c8q");
+		assert_san(promotion, "c8N", "c7c8n");
+		assert_san(promotion, "cxb8=R+", "c7b8r");
+		assert_san_error(promotion, "c8", SanError::Illegal);
+		assert_san_error(promotion, "c8=K", SanError::Invalid);
+
+		let knights = "4k3/8/8/6N1/8/8/8/K3N1N1 w - - 0 1";
+		assert_san_error(knights, "Nf3", SanError::Ambiguous);
+		assert_san_error(knights, "Ngf3", SanError::Ambiguous);
+		assert_san_error(knights, "N1f3", SanError::Ambiguous);
+		assert_san(knights, "Nef3", "e1f3");
+		assert_san(knights, "N5f3", "g5f3");
+		assert_san(knights, "Ng1f3", "g1f3");
+		assert_san(knights, "Nd3", "e1d3");
+		assert_san(knights, "Ne2", "g1e2");
+	}
+}
@@ -0,0 +1,125 @@
+use crate::*;
+use core::{fmt, ops};
+
+/// Error returned when a move in UCI notation cannot be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UciMoveError {
+	/// The string is not a syntactically valid UCI move
+	Invalid,
+	/// The move is not legal in the given position
+	Illegal,
+}
+
+impl fmt::Display for UciMoveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let s = match self {
+			UciMoveError::Invalid => "invalid UCI move",
+			UciMoveError::Illegal => "illegal move",
+		};
+		write!(f, "{s}")
+	}
+}
+
+impl std::error::Error for UciMoveError {}
+
+impl Move {
+	/// Parses a move in UCI notation (e.g. `e2e4` or `e7e8q`) and checks that it is
+	/// legal in the given position. Castling may also be written as the king
+	/// capturing its own rook (e.g. `e1h1`), as sent by Chess960-aware clients.
+	pub fn parse_uci(board: &Board, uci: &str) -> Result<Self, UciMoveError> {
+		if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+			return Err(UciMoveError::Invalid);
+		}
+		let from = Pos::try_from(&uci[0..2]).map_err(|_| UciMoveError::Invalid)?;
+		let to = Pos::try_from(&uci[2..4]).map_err(|_| UciMoveError::Invalid)?;
+		let promotion = match uci[4..].chars().next() {
+			None => None,
+			Some(ch) => match Piece::try_from_ascii_char(ch.to_ascii_lowercase()) {
+				Some((_, Piece::Pawn | Piece::King)) | None => {
+					return Err(UciMoveError::Invalid);
+				}
+				Some((_, piece)) => Some(piece),
+			},
+		};
+
+		let mut mov = Move::new(from, to, promotion);
+		// standard castling is represented as a king move, so convert it if it was
+		// written as the king capturing its own rook
+		if let Some(rook) = board.castling_rook(mov)
+			&& rook == to
+			&& from.file() == File::E
+			&& matches!(to.file(), File::A | File::H)
+		{
+			let file = if to.file() > from.file() {
+				File::G
+			} else {
+				File::C
+			};
+			mov = Move::new(from, Pos::new(file, from.rank()), promotion);
+		}
+
+		// return the generated move, which has its flags set
+		let mut legal = None;
+		board.all_moves(|m| {
+			if m == mov {
+				legal = Some(m);
+				return ops::ControlFlow::Break(());
+			}
+			ops::ControlFlow::Continue(())
+		});
+		legal.ok_or(UciMoveError::Illegal)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Board, Move, UciMoveError};
+
+	#[test]
+	fn parse_uci() {
+		let board = Board::initial_position();
+		assert_eq!(
+			Move::parse_uci(&board, "e2e4").map(|m| m.to_uci()),
+			Ok("e2e4".to_string())
+		);
+		assert_eq!(Move::parse_uci(&board, "e2e5"), Err(UciMoveError::Illegal));
+		assert_eq!(Move::parse_uci(&board, "e7e5"), Err(UciMoveError::Illegal));
+		assert_eq!(Move::parse_uci(&board, "e3e4"), Err(UciMoveError::Illegal));
+		for invalid in [
+			"", "e2", "e2e", "e2e4qq", "e2e9", "i2e4", "0000", "e2e4k", "é2e4",
+		] {
+			assert_eq!(
+				Move::parse_uci(&board, invalid),
+				Err(UciMoveError::Invalid),
+				"{invalid}"
+			);
+		}
+
+		let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+		for (uci, expected) in [
+			("e1g1", "e1g1"),
+			("e1h1", "e1g1"),
+			("e1c1", "e1c1"),
+			("e1a1", "e1c1"),
+		] {
+			assert_eq!(
+				Move::parse_uci(&board, uci).map(|m| m.to_uci()),
+				Ok(expected.to_string())
+			);
+		}
+		let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1");
+		assert_eq!(Move::parse_uci(&board, "e1h1"), Err(UciMoveError::Illegal));
+
+		let board = Board::from_fen("1r2k3/2P5/8/8/8/8/8/4K3 w - - 0 1");
+		assert_eq!(
+			Move::parse_uci(&board, "c7b8n").map(|m| m.to_uci()),
+			Ok("c7b8n".to_string())
+		);
+		assert_eq!(
+			Move::parse_uci(&board, "c7c8Q").map(|m| m.to_uci()),
+			Ok("c7c8q".to_string())
+		);
+		assert_eq!(Move::parse_uci(&board, "c7c8"), Err(UciMoveError::Illegal));
+		assert_eq!(Move::parse_uci(&board, "e1e2q"), Err(UciMoveError::Illegal));
+	}
+}
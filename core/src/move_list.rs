@@ -0,0 +1,160 @@
+use core::{fmt, ops, slice};
+
+use crate::{File, Move, Pos, Rank};
+
+/// The maximum number of legal moves in any chess position is 218
+pub(crate) const CAPACITY: usize = 256;
+
+/// A list of moves stored on the stack, as returned by [`crate::Board::legal_moves`].
+///
+/// Dereferences to a slice of moves, so `len`, `contains`, `iter` and the slice
+/// sorting methods can all be used directly.
+#[derive(Clone)]
+pub struct MoveList {
+	moves: [Move; CAPACITY],
+	len: usize,
+}
+
+impl MoveList {
+	/// Constructs an empty move list
+	pub fn new() -> Self {
+		let placeholder = Move::new(
+			Pos::new(File::A, Rank::One),
+			Pos::new(File::A, Rank::One),
+			None,
+		);
+		Self {
+			moves: [placeholder; CAPACITY],
+			len: 0,
+		}
+	}
+
+	/// Adds a move to the end of the list. Panics if the list is full.
+	pub fn push(&mut self, mov: Move) {
+		self.moves[self.len] = mov;
+		self.len += 1;
+	}
+
+	/// Removes all moves from the list
+	pub fn clear(&mut self) {
+		self.len = 0;
+	}
+}
+
+impl Default for MoveList {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ops::Deref for MoveList {
+	type Target = [Move];
+
+	fn deref(&self) -> &Self::Target {
+		&self.moves[..self.len]
+	}
+}
+
+impl ops::DerefMut for MoveList {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.moves[..self.len]
+	}
+}
+
+impl fmt::Debug for MoveList {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_list().entries(self.iter()).finish()
+	}
+}
+
+impl PartialEq for MoveList {
+	fn eq(&self, other: &Self) -> bool {
+		**self == **other
+	}
+}
+
+impl Eq for MoveList {}
+
+impl FromIterator<Move> for MoveList {
+	fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Self {
+		let mut list = Self::new();
+		for mov in iter {
+			list.push(mov);
+		}
+		list
+	}
+}
+
+impl IntoIterator for MoveList {
+	type Item = Move;
+	type IntoIter = MoveListIterator;
+
+	fn into_iter(self) -> Self::IntoIter {
+		MoveListIterator {
+			list: self,
+			index: 0,
+		}
+	}
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+	type Item = &'a Move;
+	type IntoIter = slice::Iter<'a, Move>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+pub struct MoveListIterator {
+	list: MoveList,
+	index: usize,
+}
+
+impl Iterator for MoveListIterator {
+	type Item = Move;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let mov = self.list.get(self.index).copied();
+		self.index += 1;
+		mov
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = self.list.len().saturating_sub(self.index);
+		(remaining, Some(remaining))
+	}
+}
+
+impl ExactSizeIterator for MoveListIterator {}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn legal_moves() {
+		let board = Board::initial_position();
+		let mut moves = board.legal_moves();
+		assert_eq!(moves.len(), 20);
+		assert!(!moves.is_empty());
+		let e4 = Move::from_san(&board, "e4").unwrap();
+		assert!(moves.contains(&e4));
+		assert!(!moves.contains(&Move::from_uci("e2e5")));
+
+		moves.sort_by_key(|m| (m.from(), m.to()));
+		assert_eq!(moves[0].to_uci(), "a2a3");
+		assert_eq!(moves.clone().into_iter().len(), 20);
+		assert_eq!(moves.clone().into_iter().last().unwrap().to_uci(), "h2h4");
+
+		let collected: Vec<Move> = (&moves).into_iter().copied().collect();
+		assert_eq!(collected.into_iter().collect::<MoveList>(), moves);
+	}
+
+	#[test]
+	fn empty() {
+		let board = Board::from_fen("7k/5QQ1/8/8/8/8/8/K7 b - - 0 1");
+		assert!(board.legal_moves().is_empty());
+		assert_eq!(board.legal_moves().into_iter().next(), None);
+	}
+}
@@ -0,0 +1,209 @@
+mod reader;
+
+use crate::{Board, Game, GameResult, Move, Player};
+
+pub use reader::{PgnError, PgnGame, PgnMove, PgnReader, read_pgn};
+
+/// The tags every PGN game must contain, in the order they must appear
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+	("Event", "?"),
+	("Site", "?"),
+	("Date", "????.??.??"),
+	("Round", "?"),
+	("White", "?"),
+	("Black", "?"),
+	("Result", "*"),
+];
+
+/// Maximum line length of exported movetext
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Returns the PGN result token for the given result (`1-0`, `0-1`, `1/2-1/2` or `*`).
+pub fn result_token(result: Option<GameResult>) -> &'static str {
+	match result {
+		None => "*",
+		Some(GameResult::Win {
+			winner: Player::White,
+			..
+		}) => "1-0",
+		Some(GameResult::Win {
+			winner: Player::Black,
+			..
+		}) => "0-1",
+		Some(GameResult::Draw { .. }) => "1/2-1/2",
+	}
+}
+
+fn escape(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Writes a game in Portable Game Notation.
+///
+/// The seven tag roster is always written, with `?` for any tags not given in
+/// `tags`; other tags follow in the order given. `SetUp` and `FEN` tags are added
+/// automatically if the game does not start from the initial position. The moves
+/// must be legal, starting from `start`.
+pub fn write_pgn(
+	start: &Board,
+	moves: &[Move],
+	result: Option<GameResult>,
+	tags: &[(&str, &str)],
+) -> String {
+	let mut pgn = String::new();
+	let result = result_token(result);
+	for (name, default) in SEVEN_TAG_ROSTER {
+		let value = match name {
+			"Result" => result,
+			_ => tags
+				.iter()
+				.find(|(n, _)| *n == name)
+				.map_or(default, |(_, v)| v),
+		};
+		pgn.push_str(&format!("[{name} \"{}\"]\n", escape(value)));
+	}
+	let start_fen = start.to_fen();
+	if start_fen != Board::initial_position().to_fen() {
+		pgn.push_str("[SetUp \"1\"]\n");
+		pgn.push_str(&format!("[FEN \"{start_fen}\"]\n"));
+	}
+	for (name, value) in tags {
+		if SEVEN_TAG_ROSTER.iter().any(|(n, _)| n == name) || ["SetUp", "FEN"].contains(name) {
+			continue;
+		}
+		pgn.push_str(&format!("[{name} \"{}\"]\n", escape(value)));
+	}
+	pgn.push('\n');
+
+	let mut tokens = vec![];
+	let mut board = *start;
+	for (i, &mov) in moves.iter().enumerate() {
+		match board.current_player() {
+			Player::White => tokens.push(format!("{}.", board.fullmove_number())),
+			Player::Black if i == 0 => tokens.push(format!("{}...", board.fullmove_number())),
+			Player::Black => (),
+		}
+		tokens.push(mov.format_san(board, &board.legal_moves()).to_string());
+		board.apply_move(mov);
+	}
+	tokens.push(result.to_string());
+
+	let mut line_length = 0;
+	for token in tokens {
+		if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+			pgn.push('\n');
+			line_length = 0;
+		} else if line_length > 0 {
+			pgn.push(' ');
+			line_length += 1;
+		}
+		line_length += token.len();
+		pgn.push_str(&token);
+	}
+	pgn.push('\n');
+	pgn
+}
+
+impl Game {
+	/// Writes this game in Portable Game Notation. See [`write_pgn`].
+	pub fn to_pgn(&self, tags: &[(&str, &str)]) -> String {
+		write_pgn(self.start(), self.moves(), self.result(), tags)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{DrawReason, WinReason};
+
+	fn play(start: Board, moves: &str) -> Game {
+		let mut game = Game::new(start);
+		for mov in moves.split(' ') {
+			game.play(Move::from_san(game.board(), mov).unwrap());
+		}
+		game
+	}
+
+	#[test]
+	fn result_tokens() {
+		assert_eq!(result_token(None), "*");
+		assert_eq!(
+			result_token(Some(GameResult::Win {
+				winner: Player::White,
+				win: WinReason::Checkmate
+			})),
+			"1-0"
+		);
+		assert_eq!(
+			result_token(Some(GameResult::Win {
+				winner: Player::Black,
+				win: WinReason::Resignation
+			})),
+			"0-1"
+		);
+		assert_eq!(
+			result_token(Some(GameResult::Draw {
+				draw: DrawReason::Stalemate
+			})),
+			"1/2-1/2"
+		);
+	}
+
+	#[test]
+	fn scholars_mate() {
+		let game = play(Board::initial_position(), "e4 e5 Bc4 Nc6 Qh5 Nf6 Qxf7#");
+		assert_eq!(
+			game.to_pgn(&[
+				("White", "Alice"),
+				("Black", "Bob \"B\""),
+				("Annotator", "cli")
+			]),
+			r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Alice"]
+[Black "Bob \"B\""]
+[Result "1-0"]
+[Annotator "cli"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0
+"#
+		);
+	}
+
+	#[test]
+	fn custom_start_position() {
+		let start = Board::from_fen("r3k2r/8/8/8/1p6/8/P7/R3K2R b KQkq - 3 20");
+		let mut game = play(start, "O-O-O a4 bxa3 O-O");
+		assert_eq!(
+			game.to_pgn(&[]),
+			r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "?"]
+[Black "?"]
+[Result "*"]
+[SetUp "1"]
+[FEN "r3k2r/8/8/8/1p6/8/P7/R3K2R b KQkq - 3 20"]
+
+20... O-O-O 21. a4 bxa3 22. O-O *
+"#
+		);
+		game.agree_draw();
+		assert!(game.to_pgn(&[]).ends_with("22. O-O 1/2-1/2\n"));
+	}
+
+	#[test]
+	fn line_wrapping() {
+		let moves = ["Nf3 Nf6 Ng1 Ng8"; 10];
+		let game = play(Board::initial_position(), &moves.join(" "));
+		let pgn = game.to_pgn(&[]);
+		let movetext = pgn.split("\n\n").nth(1).unwrap();
+		assert!(movetext.lines().count() > 1);
+		assert!(movetext.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+		assert!(movetext.starts_with("1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6"));
+		assert!(movetext.ends_with("1/2-1/2\n"));
+	}
+}
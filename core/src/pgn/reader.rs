@@ -0,0 +1,609 @@
+use crate::*;
+use core::{fmt, mem};
+use std::io;
+
+/// Error returned when a PGN file cannot be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnError {
+	/// Line at which the error was detected, starting at 1
+	pub line: usize,
+	/// Column (in characters) at which the error was detected, starting at 1
+	pub column: usize,
+	pub message: String,
+}
+
+impl fmt::Display for PgnError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"invalid pgn: {} at line {}, column {}",
+			self.message, self.line, self.column
+		)
+	}
+}
+
+impl std::error::Error for PgnError {}
+
+/// A move in a PGN game, along with its annotations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+	pub mov: Move,
+	/// The move as written in the PGN file
+	pub san: String,
+	/// Numeric annotation glyphs, with suffixes like `!?` converted to their NAG
+	pub nags: Vec<u8>,
+	/// Comments before this move; only used for the first move of a game or variation
+	pub comments_before: Vec<String>,
+	pub comments_after: Vec<String>,
+	/// Alternatives to this move, each starting from the position before this move
+	pub variations: Vec<Vec<PgnMove>>,
+}
+
+/// A game read from a PGN file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+	/// Tag pairs, in the order they appeared
+	pub tags: Vec<(String, String)>,
+	/// The starting position, taken from the `FEN` tag if there is one
+	pub start: Board,
+	/// The main line
+	pub moves: Vec<PgnMove>,
+	/// The game termination marker (`1-0`, `0-1`, `1/2-1/2` or `*`)
+	pub result: &'static str,
+}
+
+impl PgnGame {
+	/// Returns the value of the first tag with the given name.
+	pub fn tag(&self, name: &str) -> Option<&str> {
+		self.tags
+			.iter()
+			.find(|(n, _)| n == name)
+			.map(|(_, v)| v.as_str())
+	}
+
+	/// Converts the main line into a [`Game`]. A decisive or drawn result that
+	/// does not follow from the final position is recorded as a resignation or
+	/// draw agreement.
+	pub fn to_game(&self) -> Game {
+		let mut game = Game::new(self.start);
+		for mov in &self.moves {
+			game.play(mov.mov);
+		}
+		match self.result {
+			"1-0" => game.resign(Player::Black),
+			"0-1" => game.resign(Player::White),
+			"1/2-1/2" => game.agree_draw(),
+			_ => (),
+		}
+		game
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+	TagStart,
+	TagEnd,
+	String(String),
+	Symbol(String),
+	Period,
+	Nag(u8),
+	Comment(String),
+	VariationStart,
+	VariationEnd,
+	Eof,
+}
+
+impl fmt::Display for Token {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Token::TagStart => write!(f, "'['"),
+			Token::TagEnd => write!(f, "']'"),
+			Token::String(s) => write!(f, "string \"{s}\""),
+			Token::Symbol(s) => write!(f, "'{s}'"),
+			Token::Period => write!(f, "'.'"),
+			Token::Nag(n) => write!(f, "'${n}'"),
+			Token::Comment(_) => write!(f, "comment"),
+			Token::VariationStart => write!(f, "'('"),
+			Token::VariationEnd => write!(f, "')'"),
+			Token::Eof => write!(f, "end of input"),
+		}
+	}
+}
+
+/// Splits PGN input into tokens, reading one line at a time.
+struct Lexer<R> {
+	reader: R,
+	/// Characters of the current line, including the trailing newline
+	line: Vec<char>,
+	line_number: usize,
+	/// Index into `line` of the next character
+	column: usize,
+	peeked: Option<(Token, usize, usize)>,
+}
+
+impl<R: io::BufRead> Lexer<R> {
+	fn new(reader: R) -> Self {
+		Self {
+			reader,
+			line: vec![],
+			line_number: 0,
+			column: 0,
+			peeked: None,
+		}
+	}
+
+	fn error(&self, line: usize, column: usize, message: impl Into<String>) -> PgnError {
+		PgnError {
+			line,
+			column,
+			message: message.into(),
+		}
+	}
+
+	/// Returns the next character without consuming it, reading a new line if needed.
+	fn peek_char(&mut self) -> Result<Option<char>, PgnError> {
+		while self.column >= self.line.len() {
+			let mut line = String::new();
+			let read = self.reader.read_line(&mut line).map_err(|e| {
+				self.error(
+					self.line_number + 1,
+					1,
+					format!("failed to read input: {e}"),
+				)
+			})?;
+			if read == 0 {
+				// stay at the end of the last line so errors point there
+				self.column = self.line.len();
+				return Ok(None);
+			}
+			self.line_number += 1;
+			self.line = line.chars().collect();
+			// lines starting with '%' are escaped and must be ignored
+			self.column = if self.line.first() == Some(&'%') {
+				self.line.len()
+			} else {
+				0
+			};
+		}
+		Ok(Some(self.line[self.column]))
+	}
+
+	fn next_char(&mut self) -> Result<Option<char>, PgnError> {
+		let ch = self.peek_char()?;
+		if ch.is_some() {
+			self.column += 1;
+		}
+		Ok(ch)
+	}
+
+	fn peek(&mut self) -> Result<&Token, PgnError> {
+		if self.peeked.is_none() {
+			self.peeked = Some(self.lex()?);
+		}
+		Ok(&self.peeked.as_ref().expect("token was just peeked").0)
+	}
+
+	/// Returns the next token along with its line and column.
+	fn next(&mut self) -> Result<(Token, usize, usize), PgnError> {
+		match self.peeked.take() {
+			Some(token) => Ok(token),
+			None => self.lex(),
+		}
+	}
+
+	fn lex(&mut self) -> Result<(Token, usize, usize), PgnError> {
+		while self.peek_char()?.is_some_and(char::is_whitespace) {
+			self.column += 1;
+		}
+		let (line, column) = (self.line_number, self.column + 1);
+		let Some(ch) = self.next_char()? else {
+			return Ok((Token::Eof, line, column));
+		};
+		let token = match ch {
+			'[' => Token::TagStart,
+			']' => Token::TagEnd,
+			'(' => Token::VariationStart,
+			')' => Token::VariationEnd,
+			'.' => Token::Period,
+			'*' => Token::Symbol("*".to_string()),
+			'"' => {
+				let mut s = String::new();
+				loop {
+					match self.next_char()? {
+						Some('"') => break,
+						Some('\\') => match self.next_char()? {
+							Some(ch @ ('"' | '\\')) => s.push(ch),
+							_ => return Err(self.error(line, column, "invalid escape in string")),
+						},
+						Some('\n') | None => {
+							return Err(self.error(line, column, "unterminated string"));
+						}
+						Some(ch) => s.push(ch),
+					}
+				}
+				Token::String(s)
+			}
+			'{' => {
+				let mut s = String::new();
+				loop {
+					match self.next_char()? {
+						Some('}') => break,
+						Some(ch) => s.push(ch),
+						None => return Err(self.error(line, column, "unterminated comment")),
+					}
+				}
+				Token::Comment(s.trim().to_string())
+			}
+			';' => {
+				let mut s = String::new();
+				while let Some(ch) = self.next_char()? {
+					if ch == '\n' {
+						break;
+					}
+					s.push(ch);
+				}
+				Token::Comment(s.trim().to_string())
+			}
+			'$' => {
+				let mut s = String::new();
+				while let Some(ch) = self.peek_char()?.filter(char::is_ascii_digit) {
+					s.push(ch);
+					self.column += 1;
+				}
+				let nag = s
+					.parse()
+					.map_err(|_| self.error(line, column, "invalid NAG"))?;
+				Token::Nag(nag)
+			}
+			'!' | '?' => {
+				let mut s = ch.to_string();
+				while let Some(ch) = self.peek_char()?.filter(|&ch| ch == '!' || ch == '?') {
+					s.push(ch);
+					self.column += 1;
+				}
+				Token::Nag(match s.as_str() {
+					"!" => 1,
+					"?" => 2,
+					"!!" => 3,
+					"??" => 4,
+					"!?" => 5,
+					"?!" => 6,
+					_ => return Err(self.error(line, column, format!("invalid annotation '{s}'"))),
+				})
+			}
+			ch if ch.is_ascii_alphanumeric() => {
+				let mut s = ch.to_string();
+				while let Some(ch) = self
+					.peek_char()?
+					.filter(|&ch| ch.is_ascii_alphanumeric() || "_+#=:-/".contains(ch))
+				{
+					s.push(ch);
+					self.column += 1;
+				}
+				Token::Symbol(s)
+			}
+			ch => return Err(self.error(line, column, format!("unexpected character '{ch}'"))),
+		};
+		Ok((token, line, column))
+	}
+}
+
+fn parse_result(symbol: &str) -> Option<&'static str> {
+	["1-0", "0-1", "1/2-1/2", "*"]
+		.into_iter()
+		.find(|&token| token == symbol)
+}
+
+/// Reads games one at a time from PGN input, such as a file containing many games.
+///
+/// Moves are resolved against the position they are played in, so every move
+/// (including those in variations) must be legal. Reading stops after the first
+/// error.
+pub struct PgnReader<R> {
+	lexer: Lexer<R>,
+	failed: bool,
+}
+
+impl<R: io::BufRead> PgnReader<R> {
+	pub fn new(reader: R) -> Self {
+		Self {
+			lexer: Lexer::new(reader),
+			failed: false,
+		}
+	}
+
+	fn unexpected(&self, (token, line, column): (Token, usize, usize)) -> PgnError {
+		self.lexer
+			.error(line, column, format!("unexpected {token}"))
+	}
+
+	fn read_game(&mut self) -> Result<Option<PgnGame>, PgnError> {
+		let mut tags = vec![];
+		let mut start = Board::initial_position();
+		let mut comments = vec![];
+		loop {
+			match self.lexer.peek()? {
+				Token::Eof if tags.is_empty() => return Ok(None),
+				Token::Comment(_) => {
+					if let (Token::Comment(comment), ..) = self.lexer.next()? {
+						comments.push(comment);
+					}
+				}
+				Token::TagStart => {
+					self.lexer.next()?;
+					let name = match self.lexer.next()? {
+						(Token::Symbol(name), ..) => name,
+						token => return Err(self.unexpected(token)),
+					};
+					let (value, line, column) = match self.lexer.next()? {
+						(Token::String(value), line, column) => (value, line, column),
+						token => return Err(self.unexpected(token)),
+					};
+					match self.lexer.next()? {
+						(Token::TagEnd, ..) => (),
+						token => return Err(self.unexpected(token)),
+					}
+					if name == "FEN" {
+						start = Board::try_from_fen(&value)
+							.map_err(|e| self.lexer.error(line, column, e.to_string()))?;
+					}
+					tags.push((name, value));
+				}
+				_ => break,
+			}
+		}
+		let (moves, result) = self.read_line(start, true, comments)?;
+		Ok(Some(PgnGame {
+			tags,
+			start,
+			moves,
+			result: result.unwrap_or("*"),
+		}))
+	}
+
+	/// Reads the moves of the main line or of a variation, returning the result
+	/// token if the line ended with one.
+	fn read_line(
+		&mut self,
+		mut board: Board,
+		main_line: bool,
+		mut comments: Vec<String>,
+	) -> Result<(Vec<PgnMove>, Option<&'static str>), PgnError> {
+		let mut moves: Vec<PgnMove> = vec![];
+		let mut previous = board;
+		loop {
+			// a missing result is tolerated at the end of the input or before the next game
+			if main_line && matches!(self.lexer.peek()?, Token::Eof | Token::TagStart) {
+				return Ok((moves, None));
+			}
+			let (token, line, column) = self.lexer.next()?;
+			match token {
+				Token::Comment(comment) => match moves.last_mut() {
+					Some(mov) => mov.comments_after.push(comment),
+					None => comments.push(comment),
+				},
+				Token::Nag(nag) => match moves.last_mut() {
+					Some(mov) => mov.nags.push(nag),
+					None => return Err(self.lexer.error(line, column, "NAG before first move")),
+				},
+				Token::Period => (),
+				Token::Symbol(symbol) if symbol.chars().all(|ch| ch.is_ascii_digit()) => (),
+				Token::Symbol(symbol) if parse_result(&symbol).is_some() => {
+					if !main_line {
+						return Err(self.lexer.error(line, column, "result inside variation"));
+					}
+					return Ok((moves, parse_result(&symbol)));
+				}
+				Token::Symbol(san) => {
+					let mov = Move::from_san(&board, &san)
+						.map_err(|e| self.lexer.error(line, column, format!("{e} '{san}'")))?;
+					previous = board;
+					board.apply_move(mov);
+					moves.push(PgnMove {
+						mov,
+						san,
+						nags: vec![],
+						comments_before: mem::take(&mut comments),
+						comments_after: vec![],
+						variations: vec![],
+					});
+				}
+				Token::VariationStart if !moves.is_empty() => {
+					let (variation, _) = self.read_line(previous, false, vec![])?;
+					moves
+						.last_mut()
+						.expect("moves is not empty")
+						.variations
+						.push(variation);
+				}
+				Token::VariationEnd if !main_line => return Ok((moves, None)),
+				Token::Eof => return Err(self.lexer.error(line, column, "unterminated variation")),
+				token => return Err(self.unexpected((token, line, column))),
+			}
+		}
+	}
+}
+
+impl<R: io::BufRead> Iterator for PgnReader<R> {
+	type Item = Result<PgnGame, PgnError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.failed {
+			return None;
+		}
+		let game = self.read_game();
+		self.failed = game.is_err();
+		game.transpose()
+	}
+}
+
+/// Reads all games from a string containing PGN.
+pub fn read_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+	PgnReader::new(pgn.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tags_and_moves() {
+		let games = read_pgn(
+			r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V. \"Boris\""]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 {This opening is called the Ruy Lopez.}
+4.Ba4 Nf6 5. O-O Be7 1/2-1/2
+
+[Event "?"]
+
+1. d4 d5 *
+"#,
+		)
+		.unwrap();
+		assert_eq!(games.len(), 2);
+		let game = &games[0];
+		assert_eq!(game.tag("Site"), Some("Belgrade, Serbia JUG"));
+		assert_eq!(game.tag("Black"), Some("Spassky, Boris V. \"Boris\""));
+		assert_eq!(game.tag("Round"), None);
+		assert_eq!(game.result, "1/2-1/2");
+		let moves: Vec<_> = game.moves.iter().map(|m| m.san.as_str()).collect();
+		assert_eq!(
+			moves,
+			[
+				"e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7"
+			]
+		);
+		assert_eq!(game.moves[8].mov, Move::from_uci("e1g1"));
+		assert_eq!(
+			game.moves[5].comments_after,
+			["This opening is called the Ruy Lopez."]
+		);
+		assert_eq!(
+			game.to_game().board().to_fen(),
+			"r1bqk2r/1pppbppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 4 6"
+		);
+		assert!(game.to_game().result().is_some());
+		assert_eq!(games[1].moves.len(), 2);
+		assert_eq!(games[1].result, "*");
+	}
+
+	#[test]
+	fn comment_between_tags() {
+		let game = &read_pgn(
+			r#"[Event "Casual"]
+{exported by hand}
+[Site "Home"]
+
+1. e4 *"#,
+		)
+		.unwrap()[0];
+		assert_eq!(game.tag("Event"), Some("Casual"));
+		assert_eq!(game.tag("Site"), Some("Home"));
+		assert_eq!(game.moves.len(), 1);
+		assert_eq!(game.moves[0].comments_before, ["exported by hand"]);
+		assert_eq!(game.result, "*");
+	}
+
+	#[test]
+	fn annotations_and_variations() {
+		let game = &read_pgn(
+			"{Start} 1. e4!? $14 ; a comment\n\
+			1... e5 (1... c5 2. Nf3 (2. c3 d5) d6 {Sicilian}) (1... e6) 2. Nf3?! 1-0",
+		)
+		.unwrap()[0];
+		assert_eq!(game.moves.len(), 3);
+		assert_eq!(game.moves[0].comments_before, ["Start"]);
+		assert_eq!(game.moves[0].nags, [5, 14]);
+		assert_eq!(game.moves[0].comments_after, ["a comment"]);
+		assert_eq!(game.moves[2].nags, [6]);
+		assert_eq!(game.result, "1-0");
+
+		let variations = &game.moves[1].variations;
+		assert_eq!(variations.len(), 2);
+		let sicilian: Vec<_> = variations[0].iter().map(|m| m.san.as_str()).collect();
+		assert_eq!(sicilian, ["c5", "Nf3", "d6"]);
+		assert_eq!(variations[0][2].comments_after, ["Sicilian"]);
+		assert_eq!(
+			variations[0][1].variations[0][0].mov,
+			Move::from_uci("c2c3")
+		);
+		assert_eq!(
+			variations[0][1].variations[0][1].mov,
+			Move::from_uci("d7d5")
+		);
+		assert_eq!(variations[1][0].mov, Move::from_uci("e7e6"));
+	}
+
+	#[test]
+	fn custom_start_position() {
+		let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/R3K3 b Q - 0 40"]
+
+40... Kd7 41. O-O-O+ *"#;
+		let game = &read_pgn(pgn).unwrap()[0];
+		assert_eq!(
+			game.start,
+			Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 40")
+		);
+		assert_eq!(game.moves[1].mov, Move::from_uci("e1c1"));
+
+		// round trip through the writer
+		let game = game.to_game();
+		let written = game.to_pgn(&[]);
+		let read = &read_pgn(&written).unwrap()[0];
+		assert_eq!(read.to_game().board(), game.board());
+	}
+
+	#[test]
+	fn streaming() {
+		let pgn = "1. e4 *\n\n1. d4 *\n\n1. c4 Nf3 *\n\n1. Nf3 *\n";
+		let mut reader = PgnReader::new(pgn.as_bytes());
+		assert_eq!(reader.next().unwrap().unwrap().moves[0].san, "e4");
+		assert_eq!(reader.next().unwrap().unwrap().moves[0].san, "d4");
+		assert!(reader.next().unwrap().is_err());
+		assert!(reader.next().is_none());
+
+		// missing results
+		let games = read_pgn("1. e4 e5\n[Event \"?\"]\n1. d4").unwrap();
+		assert_eq!(games.len(), 2);
+		assert_eq!(games[0].result, "*");
+		assert_eq!(read_pgn("").unwrap(), []);
+	}
+
+	#[track_caller]
+	fn assert_error(pgn: &str, line: usize, column: usize, message: &str) {
+		assert_eq!(
+			read_pgn(pgn),
+			Err(PgnError {
+				line,
+				column,
+				message: message.to_string()
+			})
+		);
+	}
+
+	#[test]
+	fn errors() {
+		assert_error("1. e4 e5\n2. Ke3 *", 2, 4, "illegal move 'Ke3'");
+		assert_error("1. e4 e5 2. Xy3", 1, 13, "invalid SAN move 'Xy3'");
+		assert_error("[Event \"?\"\n1. e4", 2, 1, "unexpected '1'");
+		assert_error("[Event \"?]", 1, 8, "unterminated string");
+		assert_error("1. e4 {comment", 1, 7, "unterminated comment");
+		assert_error("(1. e4) *", 1, 1, "unexpected '('");
+		assert_error("1. e4 (1. d4 *", 1, 14, "result inside variation");
+		assert_error("1. e4 (1. d4", 1, 13, "unterminated variation");
+		assert_error("1. e4 e5 ) *", 1, 10, "unexpected ')'");
+		assert_error("$1 1. e4 *", 1, 1, "NAG before first move");
+		assert_error("1. e4 !!! *", 1, 7, "invalid annotation '!!!'");
+		assert_error(
+			"[FEN \"8/8/8 w - - 0 1\"]\n*",
+			1,
+			6,
+			"invalid fen: expected 8 ranks of 8 squares in piece placement at offset 5",
+		);
+	}
+}
@@ -39,6 +39,19 @@ impl Piece {
 		}
 	}
 
+	/// Returns the piece's conventional value in centipawns. The king is given a
+	/// value larger than all other pieces combined.
+	pub fn value(self) -> i32 {
+		match self {
+			Piece::Pawn => 100,
+			Piece::Knight => 300,
+			Piece::Bishop => 300,
+			Piece::Rook => 500,
+			Piece::Queen => 900,
+			Piece::King => 10000,
+		}
+	}
+
 	pub fn ascii_char(self, player: Player) -> char {
 		match (player, self) {
 			(Player::White, Piece::Pawn) => 'P',
@@ -57,7 +70,12 @@ impl Piece {
 	}
 
 	pub fn from_ascii_char(ch: char) -> (Player, Self) {
-		match ch {
+		Self::try_from_ascii_char(ch).unwrap_or_else(|| panic!("Invalid piece character: {ch}"))
+	}
+
+	/// Parses a FEN piece character, returning `None` if it is not one of `PNBRQKpnbrqk`.
+	pub fn try_from_ascii_char(ch: char) -> Option<(Player, Self)> {
+		Some(match ch {
 			'P' => (Player::White, Piece::Pawn),
 			'N' => (Player::White, Piece::Knight),
 			'B' => (Player::White, Piece::Bishop),
@@ -70,8 +88,8 @@ impl Piece {
 			'r' => (Player::Black, Piece::Rook),
 			'q' => (Player::Black, Piece::Queen),
 			'k' => (Player::Black, Piece::King),
-			_ => panic!("Invalid piece character: {ch}"),
-		}
+			_ => return None,
+		})
 	}
 
 	pub fn emoji(self, player: Player) -> char {
@@ -1,3 +1,8 @@
+use crate::consts::{
+	BISHOP_ATTACKS, BISHOP_MAGIC_MAGICS, BISHOP_MAGIC_MASKS, BISHOP_MAGIC_OFFSETS,
+	BISHOP_MAGIC_SHIFTS, ROOK_ATTACKS, ROOK_MAGIC_MAGICS, ROOK_MAGIC_MASKS, ROOK_MAGIC_OFFSETS,
+	ROOK_MAGIC_SHIFTS,
+};
 use crate::*;
 use core::fmt;
 
@@ -58,6 +63,29 @@ impl Pos {
 		self.bishop_moves() | self.rook_moves()
 	}
 
+	/// Returns the squares a bishop on this square attacks, given the occupied squares.
+	/// Each ray includes the first occupied square it hits.
+	pub fn bishop_attacks(self, occupancy: Bitboard) -> Bitboard {
+		let i = self.value() as usize;
+		let index = (occupancy.value() & BISHOP_MAGIC_MASKS[i])
+			.wrapping_mul(BISHOP_MAGIC_MAGICS[i])
+			>> BISHOP_MAGIC_SHIFTS[i];
+		Bitboard::new(BISHOP_ATTACKS[BISHOP_MAGIC_OFFSETS[i] + index as usize])
+	}
+
+	/// Returns the squares a rook on this square attacks, given the occupied squares.
+	/// Each ray includes the first occupied square it hits.
+	pub fn rook_attacks(self, occupancy: Bitboard) -> Bitboard {
+		let i = self.value() as usize;
+		let index = (occupancy.value() & ROOK_MAGIC_MASKS[i]).wrapping_mul(ROOK_MAGIC_MAGICS[i])
+			>> ROOK_MAGIC_SHIFTS[i];
+		Bitboard::new(ROOK_ATTACKS[ROOK_MAGIC_OFFSETS[i] + index as usize])
+	}
+
+	pub fn queen_attacks(self, occupancy: Bitboard) -> Bitboard {
+		self.bishop_attacks(occupancy) | self.rook_attacks(occupancy)
+	}
+
 	pub fn all_moves(self) -> Bitboard {
 		self.knight_moves() | self.bishop_moves() | self.rook_moves()
 	}
@@ -121,4 +149,47 @@ mod tests {
 			}
 		}
 	}
+
+	fn walk_rays(pos: Pos, directions: &[Direction], occupancy: Bitboard) -> Bitboard {
+		let mut result = Bitboard::empty();
+		for &direction in directions {
+			let mut current = pos;
+			while let Some(next) = current.offset(direction) {
+				result.set(next);
+				if occupancy.get(next) {
+					break;
+				}
+				current = next;
+			}
+		}
+		result
+	}
+
+	#[test]
+	fn slider_attacks() {
+		let mut state = 0x2545_f491_4f6c_dd1d_u64;
+		for i in 0..64 {
+			let pos = Pos::from_value(i);
+			for _ in 0..100 {
+				state ^= state << 13;
+				state ^= state >> 7;
+				state ^= state << 17;
+				let occupancy = Bitboard::new(state & (state >> 3));
+				assert_eq!(
+					pos.rook_attacks(occupancy),
+					walk_rays(pos, &ORTHOGONAL_DIRECTIONS, occupancy)
+				);
+				assert_eq!(
+					pos.bishop_attacks(occupancy),
+					walk_rays(pos, &DIAGONAL_DIRECTIONS, occupancy)
+				);
+			}
+		}
+		let e4 = Pos::try_from("e4").unwrap();
+		assert_eq!(e4.rook_attacks(Bitboard::empty()), e4.rook_moves());
+		assert_eq!(
+			e4.bishop_attacks(Bitboard::full()),
+			e4.adjacent() & e4.bishop_moves()
+		);
+	}
 }
@@ -98,8 +98,8 @@ enum DeclineReason {
 	#[serde(rename = "generic")]
 	#[allow(dead_code)]
 	Generic,
-	#[serde(rename = "standard")]
-	OnlyStandard,
+	#[serde(rename = "variant")]
+	UnsupportedVariant,
 	#[serde(rename = "tooSlow")]
 	TooSlow,
 	#[serde(rename = "tooFast")]
@@ -121,6 +121,14 @@ struct GameEventPlayer {
 struct GameStateEvent {
 	moves: String,
 	status: String,
+	/// "white" or "black" once a game has been won
+	winner: Option<String>,
+	/// Remaining clock times and increments, in milliseconds. Missing in games
+	/// without a clock, such as correspondence games.
+	wtime: Option<u64>,
+	btime: Option<u64>,
+	winc: Option<u64>,
+	binc: Option<u64>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -129,12 +137,12 @@ enum GameUpdate {
 	GameFull {
 		white: GameEventPlayer,
 		black: GameEventPlayer,
+		variant: Variant,
+		#[serde(rename = "initialFen")]
+		initial_fen: String,
 		state: GameStateEvent,
 	},
-	GameState {
-		moves: String,
-		status: String,
-	},
+	GameState(GameStateEvent),
 	ChatLine,
 	OpponentGone,
 }
@@ -294,7 +302,7 @@ impl Client {
 			.request::<()>(method, path, None)
 			.await?
 			.bytes_stream()
-			.map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+			.map_err(io::Error::other)
 			.into_async_read()
 			.lines()
 			.map_err(eyre::Report::from);
@@ -382,13 +390,16 @@ impl Client {
 							// we created the challenge
 							return Ok(());
 						}
-						if challenge.variant.key.as_str() != "standard" {
+						if !["standard", "chess960"].contains(&challenge.variant.key.as_str()) {
 							info!(
-								"declining challenge because it is not standard (variant: {})",
+								"declining challenge because it is not standard or chess960 (variant: {})",
 								challenge.variant.key
 							);
-							self.decline_challenge(&challenge.id, DeclineReason::OnlyStandard)
-								.await?;
+							self.decline_challenge(
+								&challenge.id,
+								DeclineReason::UnsupportedVariant,
+							)
+							.await?;
 							return Ok(());
 						}
 						if challenge.speed == "correspondence" {
@@ -431,12 +442,28 @@ impl Client {
 		Ok(())
 	}
 
-	async fn search_for_move(board: chess_core::Board) -> eyre::Result<chess_core::Move> {
-		info!("searching for move");
+	async fn search_for_move(
+		searcher: &std::sync::Arc<std::sync::Mutex<chess_core::Searcher>>,
+		board: chess_core::Board,
+		time_limit: time::Duration,
+	) -> eyre::Result<chess_core::Move> {
+		info!("searching for move ({}ms)", time_limit.as_millis());
 		let (send, recv) = tokio::sync::oneshot::channel();
+		let searcher = std::sync::Arc::clone(searcher);
 		rayon::spawn(move || {
-			let mov = chess_core::search(&board, 3, true, random_u32).unwrap();
-			send.send(mov).unwrap();
+			let limits = chess_core::SearchLimits {
+				time: Some(time_limit),
+				..chess_core::SearchLimits::default()
+			};
+			let result = searcher.lock().unwrap().search(&board, limits);
+			info!(
+				"searched to depth {} ({} nodes)",
+				result.depth, result.nodes
+			);
+			if let Some(moves) = result.mate_in() {
+				info!("found mate in {moves}");
+			}
+			send.send(result.best_move.unwrap()).unwrap();
 		});
 		Ok(recv.await?)
 	}
@@ -444,32 +471,67 @@ impl Client {
 	async fn handle_state_update(
 		&self,
 		game_id: &str,
-		status: &str,
-		moves: &str,
+		state: &GameStateEvent,
+		searcher: &std::sync::Arc<std::sync::Mutex<chess_core::Searcher>>,
 		playing_as_white: bool,
+		start: &chess_core::Board,
+		chess960: bool,
 	) -> eyre::Result<()> {
-		if status != "created" && status != "started" {
-			info!("ignoring state update: game over (status: {status})");
-			return Ok(());
+		let mut game = chess_core::Game::new(*start);
+		let status = &state.status;
+		if !state.moves.is_empty() {
+			for mov in state.moves.split(' ') {
+				let mov = chess_core::Move::parse_uci(game.board(), mov).map_err(|e| {
+					eyre::eyre!("received {e} '{mov}' from Lichess in game {game_id}")
+				})?;
+				game.play(mov);
+			}
 		}
-		let mut board = chess_core::Board::initial_position();
-		if !moves.is_empty() {
-			for mov in moves.split(' ') {
-				let mov = chess_core::Move::from_uci(mov);
-				board.apply_move(mov);
+		if status != "created" && status != "started" {
+			// record the endings the board can't detect, so the PGN has the right result
+			match (status.as_str(), state.winner.as_deref()) {
+				("resign", Some("white")) => game.resign(chess_core::Player::Black),
+				("resign", Some("black")) => game.resign(chess_core::Player::White),
+				("draw", _) => game.agree_draw(),
+				_ => (),
+			}
+			if game.result().is_some() {
+				let site = format!("https://lichess.org/{game_id}");
+				info!(
+					"ignoring state update: game over (status: {status})\n{}",
+					game.to_pgn(&[("Site", &site)])
+				);
+			} else {
+				// e.g. timeouts and aborted games, which Game has no way to record
+				info!("ignoring state update: game over (status: {status})");
 			}
+			return Ok(());
 		}
 		let my_color = if playing_as_white {
 			chess_core::Player::White
 		} else {
 			chess_core::Player::Black
 		};
-		if board.current_player != my_color {
+		if game.board().current_player() != my_color {
 			info!("ignoring state update: not our turn");
 			return Ok(());
 		}
-		let mov = Self::search_for_move(board).await?;
-		let mov_uci = mov.to_uci();
+		if let Some(result) = game.result() {
+			info!("ignoring state update: game over ({result})");
+			return Ok(());
+		}
+		let (remaining, increment) = if playing_as_white {
+			(state.wtime, state.winc)
+		} else {
+			(state.btime, state.binc)
+		};
+		let time_limit = move_time(remaining, increment);
+		let mov = Self::search_for_move(searcher, *game.board(), time_limit).await?;
+		let mov_uci = if chess960 {
+			mov.to_uci_chess960(game.board())
+		} else {
+			mov.to_uci()
+		};
 		info!("found move: {mov_uci}");
 		self.json_request::<Ok>(Method::POST, &format!("bot/game/{game_id}/move/{mov_uci}"))
 			.await?
@@ -480,6 +542,12 @@ impl Client {
 	pub async fn play_game(&self, id: &str) -> eyre::Result<()> {
 		trace!("opening game stream '{id}'");
 		let mut playing_as_white = true;
+		let mut start = chess_core::Board::initial_position();
+		let mut chess960 = false;
+		// reused for every move so the transposition table carries over
+		let searcher = std::sync::Arc::new(std::sync::Mutex::new(chess_core::Searcher::new(
+			true, random_u32, clock,
+		)));
 		let stream = self
 			.ndjson_request::<GameUpdate>(Method::GET, &format!("bot/game/stream/{id}"))
 			.await?;
@@ -489,8 +557,14 @@ impl Client {
 				GameUpdate::GameFull {
 					white,
 					black,
+					variant,
+					initial_fen,
 					state,
 				} => {
+					if initial_fen != "startpos" {
+						start = chess_core::Board::try_from_fen(&initial_fen)?;
+					}
+					chess960 = variant.key == "chess960";
 					if white.id == self.player_id {
 						playing_as_white = true;
 					} else if black.id == self.player_id {
@@ -501,12 +575,26 @@ impl Client {
 							white.id, black.id, self.player_id
 						);
 					}
-					self.handle_state_update(id, &state.status, &state.moves, playing_as_white)
-						.await?;
+					self.handle_state_update(
+						id,
+						&state,
+						&searcher,
+						playing_as_white,
+						&start,
+						chess960,
+					)
+					.await?;
 				}
-				GameUpdate::GameState { moves, status } => {
-					self.handle_state_update(id, &status, &moves, playing_as_white)
-						.await?
+				GameUpdate::GameState(state) => {
+					self.handle_state_update(
+						id,
+						&state,
+						&searcher,
+						playing_as_white,
+						&start,
+						chess960,
+					)
+					.await?
 				}
 				_ => {
 					trace!("ignoring game update: {update:#?}");
@@ -531,3 +619,25 @@ impl Client {
 fn random_u32() -> u32 {
 	rand::random()
 }
+
+fn clock() -> time::Duration {
+	time::SystemTime::now()
+		.duration_since(time::UNIX_EPOCH)
+		.unwrap()
+}
+
+/// How long to think about a move in games without a clock
+const UNTIMED_MOVE_TIME: time::Duration = time::Duration::from_secs(5);
+
+/// Decides how long to think about a move, given our remaining time and increment
+/// in milliseconds, if the game has a clock
+fn move_time(remaining_ms: Option<u64>, increment_ms: Option<u64>) -> time::Duration {
+	let Some(remaining_ms) = remaining_ms else {
+		return UNTIMED_MOVE_TIME;
+	};
+	let increment_ms = increment_ms.unwrap_or(0);
+	let budget = remaining_ms / 40 + increment_ms / 2;
+	// keep a reserve so network latency doesn't make us lose on time
+	let max = remaining_ms.saturating_sub(1000) / 2;
+	time::Duration::from_millis(budget.min(max).max(50))
+}
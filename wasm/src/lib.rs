@@ -1,5 +1,6 @@
-use chess_core::{Board, Player, search};
-use std::ops;
+use chess_core::{Board, CastlingSide, Game, Move, Player, SearchLimits, Searcher};
+use std::cell::RefCell;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -12,53 +13,94 @@ type GameState = {
 	moves: {
 		from: Square,
 		to: Square,
+		capture: boolean,
+		castling: 'kingside' | 'queenside' | null,
+		enPassant: boolean,
+		promotion: boolean,
 	}[],
 	check: boolean,
 	currentPlayer: 'white' | 'black',
 	materialDifference: number,
+	result: string | null,
 }
 
-export function game_state(fen: string): GameState;
+export function game_state(startFen: string, moves: string[]): GameState;
+
+type PositionError = {
+	message: string,
+	square: Square | null,
+}
+
+// throws if the FEN can't be parsed, e.g. if a player has two kings
+export function validate_position(fen: string): PositionError[];
 "#;
 
+/// Returns the state of a game, given its starting position and the moves played
+/// in UCI notation, so that draws by repetition can be detected
 #[wasm_bindgen(skip_typescript)]
-pub fn game_state(fen: &str) -> JsValue {
-	let board = Board::from_fen(fen);
+pub fn game_state(start_fen: &str, moves: Vec<String>) -> Result<JsValue, JsError> {
+	let game = replay(start_fen, &moves)?;
+	let board = game.board();
 	let moves = js_sys::Array::new();
-	board.all_moves(|m| {
+	for m in board.legal_moves() {
 		let mov = js_sys::Object::new();
-		set(&mov, "from", m.from.to_string());
-		set(&mov, "to", m.to.to_string());
+		set(&mov, "from", m.from().to_string());
+		set(&mov, "to", m.to().to_string());
+		set(&mov, "capture", m.captured_piece().is_some());
+		set(
+			&mov,
+			"castling",
+			m.castling_side().map(|side| match side {
+				CastlingSide::Kingside => "kingside",
+				CastlingSide::Queenside => "queenside",
+			}),
+		);
+		set(&mov, "enPassant", m.is_en_passant());
+		set(&mov, "promotion", m.promotion().is_some());
 		moves.push(&mov);
-		ops::ControlFlow::Continue(())
-	});
+	}
 	let result = js_sys::Object::new();
 	set(&result, "moves", &moves);
 	set(&result, "check", board.in_check());
-	set(&result, "currentPlayer", board.current_player.string());
+	set(&result, "currentPlayer", board.current_player().string());
 	set(&result, "materialDifference", board.material_difference());
-	result.into()
+	set(&result, "result", game.result().map(|r| r.to_string()));
+	Ok(result.into())
+}
+
+/// Returns the reasons the given position is illegal, or an empty array if it is legal.
+/// Throws if the FEN can't be parsed, which includes a second king of the same
+/// colour, since the board can't hold it.
+#[wasm_bindgen(skip_typescript)]
+pub fn validate_position(fen: &str) -> Result<JsValue, JsError> {
+	let board = Board::try_from_fen_unvalidated(fen)?;
+	let errors = js_sys::Array::new();
+	for error in board.validate().err().unwrap_or_default() {
+		let object = js_sys::Object::new();
+		set(&object, "message", error.message());
+		set(&object, "square", error.square().map(|pos| pos.to_string()));
+		errors.push(&object);
+	}
+	Ok(errors.into())
 }
 
 #[wasm_bindgen]
-pub fn apply_move(fen: &str, from: &str, to: &str, promotion: Option<char>) -> String {
-	let mut board = Board::from_fen(fen);
-	let mut mov = None;
-	board.all_moves(|m| {
-		if m.from.to_string() != from
-			|| m.to.to_string() != to
-			|| m.promotion.map(|p| p.ascii_char(Player::White)) != promotion
-		{
-			return ops::ControlFlow::Continue(());
-		}
-		mov = Some(m);
-		ops::ControlFlow::Break(())
-	});
-	let Some(mov) = mov else {
-		return "".to_string();
+pub fn apply_move(
+	fen: &str,
+	from: &str,
+	to: &str,
+	promotion: Option<char>,
+) -> Result<String, JsError> {
+	let mut board = Board::try_from_fen(fen)?;
+	let Some(mov) = board.legal_moves().into_iter().find(|m| {
+		m.from().to_string() == from
+			&& m.to().to_string() == to
+			&& m.promotion().map(|p| p.ascii_char(Player::White)) == promotion
+	}) else {
+		return Ok("".to_string());
 	};
 	board.apply_move(mov);
-	board.to_fen()
+	Ok(board.to_fen())
 }
 
 fn random_u32() -> u32 {
@@ -70,23 +112,72 @@ fn set(target: &JsValue, property_key: &str, value: impl Into<JsValue>) {
 	js_sys::Reflect::set(target, &JsValue::from(property_key), &value.into()).unwrap();
 }
 
+fn clock() -> Duration {
+	Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+thread_local! {
+	/// Kept for the whole game, so that each search can reuse the transposition table
+	static SEARCHER: RefCell<Searcher> = RefCell::new(Searcher::new(true, random_u32, clock));
+}
+
+/// Forgets what the engine learned while searching the previous game
+#[wasm_bindgen]
+pub fn new_game() {
+	SEARCHER.with_borrow_mut(Searcher::clear_hash);
+}
+
+/// Searches for the best move up to the given depth, stopping early once the time
+/// limit (in milliseconds) is reached
 #[wasm_bindgen]
-pub fn calculate_move(fen: &str, depth: usize, enable_quiescence: bool) -> String {
-	let mut board = Board::from_fen(fen);
-	let mov = search(&board, depth, enable_quiescence, random_u32);
-	let Some(mov) = mov else {
-		return "".to_string();
+pub fn calculate_move(
+	fen: &str,
+	depth: usize,
+	time_limit_ms: u32,
+	enable_quiescence: bool,
+) -> Result<String, JsError> {
+	let mut board = Board::try_from_fen(fen)?;
+	let limits = SearchLimits {
+		depth: Some(depth),
+		time: Some(Duration::from_millis(time_limit_ms.into())),
+		..SearchLimits::default()
+	};
+	let result = SEARCHER.with_borrow_mut(|searcher| {
+		searcher.set_quiescence(enable_quiescence);
+		searcher.search(&board, limits)
+	});
+	let Some(mov) = result.best_move else {
+		return Ok("".to_string());
 	};
 	board.apply_move(mov);
 	let fen = board.to_fen();
-	format!(
+	Ok(format!(
 		r#"{{
 		"from": "{}",
 		"to": "{}",
+		"uci": "{}",
 		"fen": "{fen}"
 	}}"#,
-		mov.from, mov.to,
-	)
+		mov.from(),
+		mov.to(),
+		mov.to_uci(),
+	))
+}
+
+/// Exports a game as PGN, given its starting position and the moves played in UCI notation.
+#[wasm_bindgen]
+pub fn pgn(start_fen: &str, moves: Vec<String>) -> Result<String, JsError> {
+	let game = replay(start_fen, &moves)?;
+	Ok(game.to_pgn(&[("Site", "https://chess-rust.pages.dev/")]))
+}
+
+/// Plays the given moves in UCI notation from the starting position
+fn replay(start_fen: &str, moves: &[String]) -> Result<Game, JsError> {
+	let mut game = Game::new(Board::try_from_fen(start_fen)?);
+	for mov in moves {
+		game.play(Move::parse_uci(game.board(), mov)?);
+	}
+	Ok(game)
 }
 
 #[wasm_bindgen]
@@ -18,5 +18,6 @@ function initialTheme(): Theme {
 
 export const theme = writable<Theme>(initialTheme());
 export const depth = writable(3);
+export const timeLimitMs = writable(2000);
 export const enableQuiescence = writable(true);
 export const showMaterialDifference = writable(false);
@@ -1,4 +1,11 @@
-import { default as initWasm, game_state, apply_move, init_panic_hook } from '../../../wasm/pkg';
+import {
+	default as initWasm,
+	game_state,
+	apply_move,
+	validate_position,
+	pgn,
+	init_panic_hook,
+} from '../../../wasm/pkg';
 import type { Key } from 'chessground/types';
 import MyWorker from './worker?worker';
 
@@ -8,22 +15,72 @@ init_panic_hook();
 export type CalculateMoveResult = {
 	from: Key;
 	to: Key;
+	uci: string;
 	fen: string;
 };
 
 export type CalculateMoveArgs = {
 	fen: string;
 	depth: number;
+	timeLimitMs: number;
 	enableQuiescence: boolean;
 };
 
+export type WorkerRequest =
+	| ({ type: 'search'; id: number } & CalculateMoveArgs)
+	| { type: 'newGame' };
+
+export type WorkerResponse = {
+	id: number;
+	result: string;
+};
+
 export type PromotionPiece = 'Q' | 'R' | 'B' | 'N';
 
-export function getGameState(fen: string) {
-	console.log('getting possible moves for fen', fen);
-	const gameState = game_state(fen);
+type PendingSearch = {
+	resolve: (result: CalculateMoveResult) => void;
+	reject: (reason: unknown) => void;
+};
+
+// the engine runs in a single long-lived worker, so that its transposition table
+// is reused from one move to the next
+let worker: Worker | undefined;
+let nextSearchId = 0;
+const pendingSearches: Map<number, PendingSearch> = new Map();
+
+function getWorker(): Worker {
+	if (worker !== undefined) {
+		return worker;
+	}
+	const w = new MyWorker();
+	w.onmessage = ({ data: { id, result } }: MessageEvent<WorkerResponse>) => {
+		const search = pendingSearches.get(id);
+		pendingSearches.delete(id);
+		search?.resolve(JSON.parse(result));
+	};
+	w.onerror = e => {
+		console.error(e);
+		// start over with a fresh worker for the next search
+		w.terminate();
+		worker = undefined;
+		for (const { reject } of pendingSearches.values()) {
+			reject(e);
+		}
+		pendingSearches.clear();
+	};
+	worker = w;
+	return w;
+}
+
+export function getGameState(startFen: string, moves: string[]) {
+	console.log('getting game state for moves', moves);
+	const gameState = game_state(startFen, moves);
 	const dests: Map<Key, Key[]> = new Map();
-	for (const { from, to } of gameState.moves) {
+	const promotions: Set<string> = new Set();
+	for (const { from, to, promotion } of gameState.moves) {
+		if (promotion) {
+			promotions.add(from + to);
+		}
 		const x = dests.get(from);
 		if (x !== undefined) {
 			x.push(to);
@@ -39,36 +96,44 @@ export function getGameState(fen: string) {
 
 	return {
 		dests,
+		promotions,
 		check: gameState.check,
 		currentPlayer: gameState.currentPlayer,
 		materialDifference,
+		result: gameState.result,
 	};
 }
 
 export async function calculateMove(
 	fen: string,
 	depth: number,
+	timeLimitMs: number,
 	enableQuiescence: boolean,
 ): Promise<CalculateMoveResult> {
 	const [result] = await Promise.all([
 		new Promise<CalculateMoveResult>((resolve, reject) => {
-			const w = new MyWorker();
-			w.onmessage = e => {
-				const result: { from: Key; to: Key; fen: string } = JSON.parse(e.data);
-				resolve(result);
-			};
-			w.onerror = e => {
-				console.error(e);
-				reject(e);
+			const id = nextSearchId++;
+			pendingSearches.set(id, { resolve, reject });
+			const request: WorkerRequest = {
+				type: 'search',
+				id,
+				fen,
+				depth,
+				timeLimitMs,
+				enableQuiescence,
 			};
-			const args: CalculateMoveArgs = { fen, depth, enableQuiescence };
-			w.postMessage(args);
+			getWorker().postMessage(request);
 		}),
 		sleep(200),
 	]);
 	return result;
 }
 
+export function newEngineGame() {
+	const request: WorkerRequest = { type: 'newGame' };
+	getWorker().postMessage(request);
+}
+
 function sleep(ms: number) {
 	return new Promise(resolve => setTimeout(resolve, ms));
 }
@@ -76,3 +141,15 @@ function sleep(ms: number) {
 export function applyMove(fen: string, from: Key, to: Key, promotion?: PromotionPiece) {
 	return apply_move(fen, from, to, promotion);
 }
+
+export function toUci(from: Key, to: Key, promotion?: PromotionPiece) {
+	return from + to + (promotion?.toLowerCase() ?? '');
+}
+
+export function validatePosition(fen: string) {
+	return validate_position(fen);
+}
+
+export function exportPgn(startFen: string, moves: string[]) {
+	return pgn(startFen, moves);
+}
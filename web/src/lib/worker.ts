@@ -1,12 +1,18 @@
-import { default as initWasm, calculate_move, init_panic_hook } from '../../../wasm/pkg';
-import type { CalculateMoveArgs } from './wasm';
+import { default as initWasm, calculate_move, new_game, init_panic_hook } from '../../../wasm/pkg';
+import type { WorkerRequest, WorkerResponse } from './wasm';
 
-self.addEventListener(
-	'message',
-	async ({ data: { fen, depth, enableQuiescence } }: MessageEvent<CalculateMoveArgs>) => {
-		await initWasm();
-		init_panic_hook();
-		const result = calculate_move(fen, depth, enableQuiescence);
-		postMessage(result);
-	},
-);
+const ready = initWasm().then(() => init_panic_hook());
+
+self.addEventListener('message', async ({ data }: MessageEvent<WorkerRequest>) => {
+	await ready;
+	if (data.type === 'newGame') {
+		new_game();
+		return;
+	}
+	const { id, fen, depth, timeLimitMs, enableQuiescence } = data;
+	const response: WorkerResponse = {
+		id,
+		result: calculate_move(fen, depth, timeLimitMs, enableQuiescence),
+	};
+	postMessage(response);
+});